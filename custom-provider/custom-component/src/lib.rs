@@ -2,10 +2,17 @@
 
 // wit_bindgen::generate!({ generate_all });
 mod bindings;
+use std::io::Read;
+
 use bindings::wasmcloud_tutorial::key_value_provider::store;
 use wasmcloud_component::http;
 use wasmcloud_component::http::ErrorCode; // Used for converting store errors to HTTP errors
 
+/// Response header carrying the cursor of the next page of keys, percent-encoded.
+const NEXT_CURSOR_HEADER: &str = "next-cursor";
+/// Content type of values stored with PUT without a `Content-Type` header.
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
 struct CustomComponent;
 
 http::export!(CustomComponent);
@@ -15,7 +22,7 @@ impl http::Server for CustomComponent {
         request: http::IncomingRequest,
     ) -> http::Result<http::Response<impl http::OutgoingBody>> {
         // Consume the request to get parts (headers, URI)
-        let (parts, _body) = request.into_parts();
+        let (parts, mut body) = request.into_parts();
 
         // Extract the query string from the URI
         let query = parts
@@ -24,63 +31,185 @@ impl http::Server for CustomComponent {
             .map(ToString::to_string)
             .unwrap_or_default();
 
-        // Determine the action (SET or GET) based on the query string
-        let (status, response_body) = match query.split_once('=') {
-            // Case 1: Query contains '=', implying SET operation (e.g., ?key=value)
-            Some((key, value)) => {
-                // TODO: use the `store::set` method to store the key and value using the provider.
-                (
-                     http::StatusCode::CREATED,
-                    // TODO: and a message that tells the client that the store action was
-                    // successful. You can use the `format!` macro for this.
-                )
-            }
-            // Case 2: Query does not contain '=', implying GET operation or Welcome message
-            None => {
-                let key = query.trim();
+        // `GET /keys` lists the stored keys, every other path works on the key in the query
+        if parts.uri.path() == "/keys" {
+            return match parts.method {
+                http::Method::GET => list_keys(&query),
+                _ => respond(
+                    http::StatusCode::METHOD_NOT_ALLOWED,
+                    "Use GET /keys?cursor=<cursor> to list keys.\n".into(),
+                ),
+            };
+        }
+        let key = query.trim();
 
-                if key.is_empty() {
-                    (
-                        // TODO: the key is empty, return a "BAD_REQUEST" error code. See the
-                        // CREATED error code above for reference.
-                        ,
-                        // TODO: return a meaningful error message to tell the user how to use the
-                        // API.
-                    )
-                } else {
-                    match store::get(key) {
-                        // Success: Value found
-                        Some(value) => (
-                            // TODO: the key is empty, return a "OK" error code. See the
-                            // CREATED error code above for reference
-                            ,
-                            // TODO: return a message to the client telling it what value was
-                            // stored for the requested key. You can use the `format!` macro for
-                            // this.
-                        ),
-                        // Success: Key not found
-                        None => (
-                            // TODO: the key is empty, return a "NOT_FOUND" error code. See the
-                            // CREATED error code above for reference.
-                            ,
-                            // TODO: return a message to the client telling it that no value was
-                            // found for the requested key.
-                        ),
-                    }
+        // Determine the action from the method and the query string
+        let (status, response_body) = match parts.method {
+            // DELETE ?key removes the key
+            http::Method::DELETE if key.is_empty() || key.contains('=') => (
+                http::StatusCode::BAD_REQUEST,
+                "Use the query string: ?key (DELETE).\n".into(),
+            ),
+            http::Method::DELETE => match store::delete(key) {
+                Ok(()) => (http::StatusCode::OK, format!("{key} deleted!\n")),
+                Err(e) => store_error(key, e),
+            },
+            // PUT ?key stores the request body, along with its content type
+            http::Method::PUT if key.is_empty() || key.contains('=') => (
+                http::StatusCode::BAD_REQUEST,
+                "Use the query string: ?key (PUT), with the value as the request body.\n".into(),
+            ),
+            http::Method::PUT => {
+                let content_type = parts
+                    .headers
+                    .get(http::header::CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or(DEFAULT_CONTENT_TYPE)
+                    .to_string();
+                let mut data = Vec::new();
+                if let Err(e) = body.read_to_end(&mut data) {
+                    eprintln!("Error reading request body: {}", e);
+                    return respond(
+                        http::StatusCode::BAD_REQUEST,
+                        "Failed to read the request body.\n".into(),
+                    );
+                }
+                let size = data.len();
+                match store::set_bytes(key, &store::Blob { content_type, data }) {
+                    Ok(()) => (
+                        http::StatusCode::CREATED,
+                        format!("{key} stored ({size} bytes)!\n"),
+                    ),
+                    Err(e) => store_error(key, e),
                 }
             }
+            // HEAD ?key checks whether the key exists, without transferring its value
+            http::Method::HEAD => match store::exists(key) {
+                Ok(true) => (http::StatusCode::OK, String::new()),
+                Ok(false) => (http::StatusCode::NOT_FOUND, String::new()),
+                Err(e) => (store_error(key, e).0, String::new()),
+            },
+            http::Method::GET => match query.split_once('=') {
+                // Case 1: Query contains '=', implying SET operation (e.g., ?key=value)
+                Some((key, value)) => match store::set(key, value) {
+                    Ok(()) => (
+                        http::StatusCode::CREATED,
+                        format!("{key} added with value: {value}!\n"),
+                    ),
+                    Err(e) => store_error(key, e),
+                },
+                // Case 2: Query does not contain '=', implying GET operation or Welcome message
+                None if key.is_empty() => (
+                    http::StatusCode::BAD_REQUEST,
+                    "Use the query string: ?key=value (SET) or ?key (GET).\n".into(),
+                ),
+                None => match store::get_bytes(key) {
+                    // Success: Value found, returned as stored
+                    Ok(value) => {
+                        let response = http::Response::builder()
+                            .status(http::StatusCode::OK)
+                            .header(http::header::CONTENT_TYPE, value.content_type);
+                        return finish(response, value.data);
+                    }
+                    // Failure: Key not found, or the store refused the request
+                    Err(e) => store_error(key, e),
+                },
+            },
+            _ => (
+                http::StatusCode::METHOD_NOT_ALLOWED,
+                "Use GET, PUT, HEAD or DELETE.\n".into(),
+            ),
         };
 
-        let response = http::Response::builder().status(status).body(response_body);
+        respond(status, response_body)
+    }
+}
 
-        match response {
-            Ok(r) => Ok(r),
-            Err(e) => {
-                eprintln!("Error building response: {}", e);
-                Err(ErrorCode::InternalError(Some(
-                    "Failed to finalize HTTP response".to_string(),
-                )))
-            }
+/// List a page of keys, one per line, starting after the `cursor` query parameter if given.
+///
+/// Keys are percent-encoded like the cursor, so that a key containing a line break cannot pass for
+/// several keys. If more keys follow, the cursor of the next page is returned in the `next-cursor` header.
+fn list_keys(query: &str) -> http::Result<http::Response<Vec<u8>>> {
+    let cursor = url::form_urlencoded::parse(query.as_bytes())
+        .find(|(name, _)| name == "cursor")
+        .map(|(_, cursor)| cursor.into_owned());
+    let page = match store::list_keys(cursor.as_deref()) {
+        Ok(page) => page,
+        Err(e) => {
+            let (status, body) = store_error("", e);
+            return respond(status, body);
+        }
+    };
+    let body: String = page
+        .keys
+        .iter()
+        .map(|key| format!("{}\n", encode(key)))
+        .collect();
+    let mut response = http::Response::builder().status(http::StatusCode::OK);
+    if let Some(cursor) = page.cursor {
+        response = response.header(NEXT_CURSOR_HEADER, encode(&cursor));
+    }
+    finish(response, body.into_bytes())
+}
+
+/// Percent-encode a key or cursor for a response, like a query parameter value.
+fn encode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+/// Map an error of the store to the HTTP status and message describing it.
+///
+/// | Error            | Status                     |
+/// | ---------------- | -------------------------- |
+/// | `not-found`      | 404 Not Found              |
+/// | `quota-exceeded` | 413 Payload Too Large      |
+/// | `invalid-key`    | 400 Bad Request            |
+/// | `backend`        | 503 Service Unavailable    |
+/// | `invalid-value`  | 415 Unsupported Media Type |
+fn store_error(key: &str, error: store::StoreError) -> (http::StatusCode, String) {
+    match error {
+        store::StoreError::NotFound => (
+            http::StatusCode::NOT_FOUND,
+            format!("Key '{key}' not found.\n"),
+        ),
+        store::StoreError::QuotaExceeded(reason) => (
+            http::StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Quota exceeded: {reason}.\n"),
+        ),
+        store::StoreError::InvalidKey(reason) => (
+            http::StatusCode::BAD_REQUEST,
+            format!("Invalid key '{key}': {reason}.\n"),
+        ),
+        store::StoreError::InvalidValue(reason) => (
+            http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Invalid value for '{key}': {reason}.\n"),
+        ),
+        store::StoreError::Backend(reason) => {
+            eprintln!("Store failed: {reason}");
+            (
+                http::StatusCode::SERVICE_UNAVAILABLE,
+                "The store is unavailable, try again later.\n".into(),
+            )
+        }
+    }
+}
+
+/// Finish a response with the given status and body.
+fn respond(status: http::StatusCode, body: String) -> http::Result<http::Response<Vec<u8>>> {
+    finish(http::Response::builder().status(status), body.into_bytes())
+}
+
+fn finish(
+    response: http::response::Builder,
+    body: Vec<u8>,
+) -> http::Result<http::Response<Vec<u8>>> {
+    match response.body(body) {
+        Ok(r) => Ok(r),
+        Err(e) => {
+            eprintln!("Error building response: {}", e);
+            Err(ErrorCode::InternalError(Some(
+                "Failed to finalize HTTP response".to_string(),
+            )))
         }
     }
 }
@@ -1,7 +1,20 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 
+use anyhow::{bail, Context as _};
 use serde::{Deserialize, Serialize};
 
+/// Number of write-ahead log records after which a snapshot is written, unless configured
+/// otherwise.
+const DEFAULT_SNAPSHOT_INTERVAL: usize = 1000;
+
+/// Maximum length of a key in bytes, unless configured otherwise.
+const DEFAULT_MAX_KEY_LENGTH: usize = 256;
+/// Maximum size of a value in bytes, unless configured otherwise.
+const DEFAULT_MAX_VALUE_SIZE: usize = 1024 * 1024;
+/// Maximum number of keys in a keyspace, unless configured otherwise.
+const DEFAULT_MAX_KEYS: usize = 100_000;
+
 /// Configuration for this provider, which is passed to the provider from the host.
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ProviderConfig {
@@ -19,3 +32,187 @@ impl From<&HashMap<String, String>> for ProviderConfig {
         }
     }
 }
+
+/// What happens to the keys of a link when it is deleted, see [`ProviderConfig::retention`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retention {
+    /// The keys are kept, and seen again if the link is put back.
+    Retain,
+    /// The keys are removed, unless another link still uses the same bucket.
+    Delete,
+}
+
+/// Limits on the keys and values a component may store, see [`ProviderConfig::limits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum length of a key in bytes.
+    pub max_key_length: usize,
+    /// Maximum size of a value in bytes.
+    pub max_value_size: usize,
+    /// Maximum number of keys in a keyspace.
+    pub max_keys: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_key_length: DEFAULT_MAX_KEY_LENGTH,
+            max_value_size: DEFAULT_MAX_VALUE_SIZE,
+            max_keys: DEFAULT_MAX_KEYS,
+        }
+    }
+}
+
+/// Where the provider keeps its values, see [`ProviderConfig::storage`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageConfig {
+    /// Values are kept in memory only and lost when the provider stops.
+    Memory,
+    /// Values are kept in memory and persisted to a write-ahead log and snapshots in `data_dir`.
+    Disk {
+        data_dir: PathBuf,
+        /// Number of log records after which a snapshot replaces the log.
+        snapshot_interval: usize,
+    },
+}
+
+impl ProviderConfig {
+    /// Read the retention policy from the `retention` value, `retain` (the default) or `delete`.
+    pub fn retention(&self) -> anyhow::Result<Retention> {
+        match self.values.get("retention").map(String::as_str) {
+            None | Some("retain") => Ok(Retention::Retain),
+            Some("delete") => Ok(Retention::Delete),
+            Some(other) => bail!("unknown retention {other:?}, expected retain or delete"),
+        }
+    }
+
+    /// Read the limits from the `max-key-length`, `max-value-size` and `max-keys` values.
+    pub fn limits(&self) -> anyhow::Result<Limits> {
+        let defaults = Limits::default();
+        Ok(Limits {
+            max_key_length: self.positive("max-key-length", defaults.max_key_length)?,
+            max_value_size: self.positive("max-value-size", defaults.max_value_size)?,
+            max_keys: self.positive("max-keys", defaults.max_keys)?,
+        })
+    }
+
+    /// Read the positive number `name`, `default` if it is not set.
+    fn positive(&self, name: &str, default: usize) -> anyhow::Result<usize> {
+        match self.values.get(name) {
+            Some(value) => value
+                .parse()
+                .ok()
+                .filter(|value| *value > 0)
+                .with_context(|| format!("invalid {name} {value:?}")),
+            None => Ok(default),
+        }
+    }
+
+    /// Read the storage backend from the `storage` value, `memory` (the default) or `disk`.
+    ///
+    /// The `disk` backend requires a `data-dir` to keep its files in, and writes a snapshot every
+    /// `snapshot-interval` writes (1000 by default).
+    pub fn storage(&self) -> anyhow::Result<StorageConfig> {
+        match self.values.get("storage").map(String::as_str) {
+            None | Some("memory") => Ok(StorageConfig::Memory),
+            Some("disk") => {
+                let Some(data_dir) = self.values.get("data-dir") else {
+                    bail!("the disk storage requires a data-dir");
+                };
+                let snapshot_interval =
+                    self.positive("snapshot-interval", DEFAULT_SNAPSHOT_INTERVAL)?;
+                Ok(StorageConfig::Disk {
+                    data_dir: PathBuf::from(data_dir),
+                    snapshot_interval,
+                })
+            }
+            Some(other) => bail!("unknown storage {other:?}, expected memory or disk"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(values: &[(&str, &str)]) -> ProviderConfig {
+        let values: HashMap<String, String> = values
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        ProviderConfig::from(&values)
+    }
+
+    #[test]
+    fn defaults_apply_without_values() {
+        let config = config(&[]);
+        assert_eq!(config.retention().unwrap(), Retention::Retain);
+        assert_eq!(config.limits().unwrap(), Limits::default());
+        assert_eq!(config.storage().unwrap(), StorageConfig::Memory);
+    }
+
+    #[test]
+    fn retention_is_read() {
+        let delete = config(&[("retention", "delete")]);
+        assert_eq!(delete.retention().unwrap(), Retention::Delete);
+        let retain = config(&[("retention", "retain")]);
+        assert_eq!(retain.retention().unwrap(), Retention::Retain);
+        assert!(config(&[("retention", "forever")]).retention().is_err());
+    }
+
+    #[test]
+    fn limits_are_read() {
+        let config = config(&[("max-key-length", "16"), ("max-keys", "3")]);
+        assert_eq!(
+            config.limits().unwrap(),
+            Limits {
+                max_key_length: 16,
+                max_value_size: DEFAULT_MAX_VALUE_SIZE,
+                max_keys: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn limits_must_be_positive_numbers() {
+        for value in ["0", "-1", "ten", ""] {
+            assert!(config(&[("max-value-size", value)]).limits().is_err());
+        }
+    }
+
+    #[test]
+    fn disk_storage_is_read() {
+        let default = config(&[("storage", "disk"), ("data-dir", "/var/lib/kv")]);
+        assert_eq!(
+            default.storage().unwrap(),
+            StorageConfig::Disk {
+                data_dir: PathBuf::from("/var/lib/kv"),
+                snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            }
+        );
+        let configured = config(&[
+            ("storage", "disk"),
+            ("data-dir", "/var/lib/kv"),
+            ("snapshot-interval", "10"),
+        ]);
+        assert!(matches!(
+            configured.storage().unwrap(),
+            StorageConfig::Disk {
+                snapshot_interval: 10,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn invalid_storage_is_rejected() {
+        assert!(config(&[("storage", "tape")]).storage().is_err());
+        assert!(config(&[("storage", "disk")]).storage().is_err());
+        let interval = config(&[
+            ("storage", "disk"),
+            ("data-dir", "/var/lib/kv"),
+            ("snapshot-interval", "0"),
+        ]);
+        assert!(interval.storage().is_err());
+    }
+}
@@ -1,5 +1,6 @@
 mod config;
 mod provider;
+mod storage;
 
 use provider::KeyValueStoreProvider;
 
@@ -4,21 +4,29 @@ use std::sync::Arc;
 use anyhow::Context as _;
 use anyhow::Result;
 use tokio::sync::RwLock;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use wasmcloud_provider_sdk::initialize_observability;
 use wasmcloud_provider_sdk::{
     run_provider, serve_provider_exports, Context, LinkConfig, LinkDeleteInfo, Provider,
     ProviderInitConfig,
 };
 
-use crate::config::ProviderConfig;
-use bindings::exports::wasmcloud_tutorial::key_value_provider::store::Handler;
+use crate::config::{Limits, ProviderConfig, Retention};
+use crate::storage::{self, MemoryStorage, Storage, Value};
+use bindings::exports::wasmcloud_tutorial::key_value_provider::store::{
+    Blob, Handler, KeyPage, StoreError,
+};
+
+/// Maximum number of keys returned by `list-keys` at once.
+const PAGE_SIZE: usize = 100;
+/// Link configuration key naming a bucket shared by all components linked with the same name.
+const BUCKET_KEY: &str = "bucket";
 
 pub(crate) mod bindings {
     wit_bindgen_wrpc::generate! {}
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 /// Your provider struct is where you can store any state or configuration that your provider needs to keep track of.
 pub struct KeyValueStoreProvider {
     config: Arc<RwLock<ProviderConfig>>,
@@ -26,9 +34,22 @@ pub struct KeyValueStoreProvider {
     linked_from: Arc<RwLock<HashMap<String, HashMap<String, String>>>>,
     /// All components this provider is linked to and their config
     linked_to: Arc<RwLock<HashMap<String, HashMap<String, String>>>>,
-    /// Add hashmap to store key value pairs
-    // TODO: create a HashMap with keys and values of type String. The name of the field should be
-    // "store".
+    /// Backend keeping the key value pairs, selected by the provider configuration in `init`
+    store: Arc<RwLock<Box<dyn Storage>>>,
+    /// Limits on the keys and values components may store, read from the provider configuration
+    limits: Arc<RwLock<Limits>>,
+}
+
+impl Default for KeyValueStoreProvider {
+    fn default() -> Self {
+        KeyValueStoreProvider {
+            config: Arc::default(),
+            linked_from: Arc::default(),
+            linked_to: Arc::default(),
+            store: Arc::new(RwLock::new(Box::new(MemoryStorage::default()))),
+            limits: Arc::default(),
+        }
+    }
 }
 
 /// This `impl` block is where you can implement additional methods for your provider. We've provided two examples
@@ -38,6 +59,89 @@ impl KeyValueStoreProvider {
         "custom-template-provider"
     }
 
+    /// Keyspace holding the keys of the component making an invocation.
+    ///
+    /// Components only ever see the keys of their own keyspace, so an invocation from a component
+    /// that is not linked to this provider is refused.
+    async fn keyspace(&self, ctx: Option<Context>) -> anyhow::Result<String> {
+        let source_id = ctx
+            .and_then(|ctx| ctx.component)
+            .context("invocation does not name its source component")?;
+        let linked_from = self.linked_from.read().await;
+        let config = linked_from
+            .get(&source_id)
+            .with_context(|| format!("component {source_id} is not linked to this provider"))?;
+        Ok(keyspace(&source_id, config))
+    }
+
+    /// Check that `key` is neither empty nor longer than allowed.
+    async fn check_key(&self, key: &str) -> Result<(), StoreError> {
+        let max_key_length = self.limits.read().await.max_key_length;
+        if key.is_empty() {
+            Err(StoreError::InvalidKey("key is empty".into()))
+        } else if key.len() > max_key_length {
+            Err(StoreError::InvalidKey(format!(
+                "key is longer than {max_key_length} bytes"
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Run `change` on the storage backend with exclusive access. It runs on a blocking thread, as
+    /// persistent backends write to disk before they return.
+    async fn update<T: Send + 'static>(
+        &self,
+        change: impl FnOnce(&mut dyn Storage) -> T + Send + 'static,
+    ) -> anyhow::Result<T> {
+        let mut store = Arc::clone(&self.store).write_owned().await;
+        tokio::task::spawn_blocking(move || change(&mut **store))
+            .await
+            .context("storage backend task failed")
+    }
+
+    /// Retrieve the value of `key` for the component making an invocation.
+    async fn read(
+        &self,
+        ctx: Option<Context>,
+        key: String,
+    ) -> anyhow::Result<Result<Value, StoreError>> {
+        let keyspace = self.keyspace(ctx).await?;
+        if let Err(e) = self.check_key(&key).await {
+            return Ok(Err(e));
+        }
+        let store = self.store.read().await;
+        Ok(match store.get(&keyspace, &key) {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => Err(StoreError::NotFound),
+            Err(e) => Err(backend_error(e)),
+        })
+    }
+
+    /// Store `value` under `key` for the component making an invocation, within the limits.
+    async fn write(
+        &self,
+        ctx: Option<Context>,
+        key: String,
+        value: Value,
+    ) -> anyhow::Result<Result<(), StoreError>> {
+        let keyspace = self.keyspace(ctx).await?;
+        if let Err(e) = self.check_key(&key).await {
+            return Ok(Err(e));
+        }
+        let limits = *self.limits.read().await;
+        if value.data.len() > limits.max_value_size {
+            return Ok(Err(StoreError::QuotaExceeded(format!(
+                "value is larger than {} bytes",
+                limits.max_value_size
+            ))));
+        }
+        let result = self
+            .update(move |store| set_within_limits(store, &keyspace, key, value, limits.max_keys))
+            .await?;
+        Ok(result.unwrap_or_else(|e| Err(backend_error(e))))
+    }
+
     /// Execute the provider, loading [`HostData`] from the host which includes the provider's configuration and
     /// information about the host. Once you use the passed configuration to construct a [`KeyValueStoreProvider`],
     /// you can run the provider by calling `run_provider` and then serving the provider's exports on the proper
@@ -82,28 +186,149 @@ impl KeyValueStoreProvider {
 impl Handler<Option<Context>> for KeyValueStoreProvider {
     async fn get(
         &self,
-        _ctx: Option<Context>,
+        ctx: Option<Context>,
         key: String,
-    ) -> Result<Option<String>, anyhow::Error> {
-        // TODO: implement the function logic. It should get the store out of the RWLock using the
-        // `read` method and await the promise. Then use the `get` method on the HashMap to
-        // retrieve the key and return it wrapped in a Ok. You might need to clone some data to
-        // allow this.
+    ) -> anyhow::Result<Result<String, StoreError>> {
+        Ok(self.read(ctx, key).await?.and_then(|value| {
+            String::from_utf8(value.data).map_err(|_| {
+                StoreError::InvalidValue(format!(
+                    "value of type {} is not text, read it with get-bytes",
+                    value.content_type
+                ))
+            })
+        }))
     }
 
     async fn set(
         &self,
-        _ctx: Option<Context>,
+        ctx: Option<Context>,
         key: String,
         value: String,
-    ) -> Result<(), anyhow::Error> {
-        // TODO: implement the function logic. It should get the store out of the RWLock using the
-        // `write` method (since we plan to write to the HashMap) and await the promise. Then use
-        // the `insert` method on the HashMap to write they key-value pair to it. The return value
-        // is already provided.
-        Ok(())
+    ) -> anyhow::Result<Result<(), StoreError>> {
+        self.write(ctx, key, Value::text(value)).await
+    }
+
+    async fn get_bytes(
+        &self,
+        ctx: Option<Context>,
+        key: String,
+    ) -> anyhow::Result<Result<Blob, StoreError>> {
+        Ok(self.read(ctx, key).await?.map(|value| Blob {
+            content_type: value.content_type,
+            data: value.data.into(),
+        }))
     }
+
+    async fn set_bytes(
+        &self,
+        ctx: Option<Context>,
+        key: String,
+        value: Blob,
+    ) -> anyhow::Result<Result<(), StoreError>> {
+        if value.content_type.trim().is_empty() {
+            return Ok(Err(StoreError::InvalidValue(
+                "content type is empty".into(),
+            )));
+        }
+        let value = Value {
+            content_type: value.content_type,
+            data: value.data.into(),
+        };
+        self.write(ctx, key, value).await
+    }
+
+    async fn delete(
+        &self,
+        ctx: Option<Context>,
+        key: String,
+    ) -> anyhow::Result<Result<(), StoreError>> {
+        let keyspace = self.keyspace(ctx).await?;
+        if let Err(e) = self.check_key(&key).await {
+            return Ok(Err(e));
+        }
+        let deleted = self
+            .update(move |store| store.delete(&keyspace, &key))
+            .await?;
+        Ok(match deleted {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(StoreError::NotFound),
+            Err(e) => Err(backend_error(e)),
+        })
+    }
+
+    async fn exists(
+        &self,
+        ctx: Option<Context>,
+        key: String,
+    ) -> anyhow::Result<Result<bool, StoreError>> {
+        let keyspace = self.keyspace(ctx).await?;
+        if let Err(e) = self.check_key(&key).await {
+            return Ok(Err(e));
+        }
+        let store = self.store.read().await;
+        Ok(store
+            .get(&keyspace, &key)
+            .map(|value| value.is_some())
+            .map_err(backend_error))
+    }
+
+    /// The cursor is the last key of the previous page, so that pages stay consistent while keys
+    /// are added or removed in between.
+    async fn list_keys(
+        &self,
+        ctx: Option<Context>,
+        cursor: Option<String>,
+    ) -> anyhow::Result<Result<KeyPage, StoreError>> {
+        let keyspace = self.keyspace(ctx).await?;
+        let store = self.store.read().await;
+        // read one key more than returned, to know whether another page follows
+        let mut keys = match store.list_keys(&keyspace, cursor.as_deref(), PAGE_SIZE + 1) {
+            Ok(keys) => keys,
+            Err(e) => return Ok(Err(backend_error(e))),
+        };
+        let cursor = if keys.len() > PAGE_SIZE {
+            keys.truncate(PAGE_SIZE);
+            keys.last().cloned()
+        } else {
+            None
+        };
+        Ok(Ok(KeyPage { keys, cursor }))
+    }
+}
+
+/// Set `key` to `value` in `keyspace`, unless that would add a key to a keyspace already holding
+/// `max_keys` keys. Replacing the value of an existing key is always allowed.
+fn set_within_limits(
+    store: &mut dyn Storage,
+    keyspace: &str,
+    key: String,
+    value: Value,
+    max_keys: usize,
+) -> Result<Result<(), StoreError>> {
+    if store.get(keyspace, &key)?.is_none() && store.count(keyspace)? >= max_keys {
+        return Ok(Err(StoreError::QuotaExceeded(format!(
+            "keyspace already holds {max_keys} keys"
+        ))));
+    }
+    store.set(keyspace, key, value)?;
+    Ok(Ok(()))
+}
+
+/// Report a failure of the storage backend to the component, keeping the details in the log.
+fn backend_error(e: anyhow::Error) -> StoreError {
+    warn!("storage backend failed: {e:#}");
+    StoreError::Backend(e.to_string())
 }
+
+/// Keyspace of the component `source_id` linked with `config`: the bucket named by the link, if
+/// any, or else a keyspace of the component's own.
+fn keyspace(source_id: &str, config: &HashMap<String, String>) -> String {
+    match config.get(BUCKET_KEY).filter(|bucket| !bucket.is_empty()) {
+        Some(bucket) => format!("bucket:{bucket}"),
+        None => format!("component:{source_id}"),
+    }
+}
+
 /// Implementing the [`Provider`] trait is optional. Implementing the methods in the trait allow you to set up
 /// custom logic for handling links, deletions, and shutdowns. This is useful to set up any connections, state,
 /// resources, or cleanup that your provider needs to do when it is linked to or unlinked from a component.
@@ -116,7 +341,19 @@ impl Provider for KeyValueStoreProvider {
         info!(provider_id, ?initial_config, "initializing provider");
 
         // Save configuration to provider state
-        *self.config.write().await = ProviderConfig::from(initial_config);
+        let config = ProviderConfig::from(initial_config);
+        let storage = config.storage().context("invalid storage configuration")?;
+        config
+            .retention()
+            .context("invalid retention configuration")?;
+        let limits = config.limits().context("invalid limits configuration")?;
+        info!(?storage, ?limits, "opening storage");
+        let store = tokio::task::spawn_blocking(move || storage::open(&storage))
+            .await
+            .context("storage backend task failed")??;
+        *self.store.write().await = store;
+        *self.limits.write().await = limits;
+        *self.config.write().await = config;
 
         Ok(())
     }
@@ -191,9 +428,29 @@ impl Provider for KeyValueStoreProvider {
     /// When a link is deleted from a component to your provider, this method will be called with the source ID
     /// of the component that was unlinked. You can use this method to clean up any state or resources that were
     /// associated with the linked component.
+    ///
+    /// Here, the keys of the component are removed if the `retention` configuration asks for it,
+    /// unless they are in a bucket still shared with another linked component.
     async fn delete_link_as_target(&self, link: impl LinkDeleteInfo) -> anyhow::Result<()> {
         let source_id = link.get_source_id();
-        self.linked_from.write().await.remove(source_id);
+        let mut linked_from = self.linked_from.write().await;
+        if let Some(config) = linked_from.remove(source_id) {
+            let unlinked = keyspace(source_id, &config);
+            let shared = linked_from
+                .iter()
+                .any(|(other_id, other_config)| keyspace(other_id, other_config) == unlinked);
+            let retention = self.config.read().await.retention()?;
+            if retention == Retention::Delete && !shared {
+                let keyspace = unlinked.clone();
+                self.update(move |store| store.clear(&keyspace)).await??;
+                info!(
+                    source_id,
+                    keyspace = %unlinked,
+                    "deleted the keys of an unlinked component"
+                );
+            }
+        }
+        drop(linked_from);
 
         debug!(
             "finished processing delete link from component [{}] to provider",
@@ -207,6 +464,8 @@ impl Provider for KeyValueStoreProvider {
     async fn shutdown(&self) -> anyhow::Result<()> {
         self.linked_from.write().await.clear();
         self.linked_to.write().await.clear();
+        // Compact the persisted values, so that the next start does not replay a long log
+        self.update(|store| store.checkpoint()).await??;
 
         Ok(())
     }
@@ -0,0 +1,553 @@
+//! Storage backends keeping the values of the key-value store.
+//!
+//! Values are grouped into keyspaces, so that every linked component, or every group of components
+//! sharing a bucket, sees only its own keys. The provider talks to its values through the
+//! [`Storage`] trait, so that where they are kept can be chosen through the provider
+//! configuration, see [`ProviderConfig::storage`]:
+//!
+//! - [`MemoryStorage`] keeps the values in memory. They are lost when the provider stops.
+//! - [`DiskStorage`] additionally appends every write to a write-ahead log before applying it, and
+//!   regularly replaces the log with a snapshot of all values. When the provider starts, the
+//!   snapshot is loaded and the log written since is replayed.
+//!
+//! Every value is kept as bytes with its content type, see [`Value`].
+//!
+//! [`ProviderConfig::storage`]: crate::config::ProviderConfig::storage
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write as _};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context as _, Result};
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tracing::{error, info, warn};
+
+use crate::config::StorageConfig;
+
+/// Name of the snapshot file in the data directory.
+const SNAPSHOT_FILE: &str = "snapshot.json";
+/// Name of the write-ahead log in the data directory.
+const WAL_FILE: &str = "wal.log";
+/// Content type of the values stored as strings.
+pub const TEXT_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// A stored value: its bytes and the media type describing them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Value {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Value {
+    /// A value stored as a string, with the content type [`TEXT_CONTENT_TYPE`].
+    pub fn text(text: String) -> Value {
+        Value {
+            content_type: TEXT_CONTENT_TYPE.to_owned(),
+            data: text.into_bytes(),
+        }
+    }
+}
+
+/// How a [`Value`] is written to the log and snapshots.
+///
+/// Text values are written as plain strings, as they were before binary values were supported, so
+/// that existing files can still be read. Other values are written with their data in base64.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Encoded<'a> {
+    Text(Cow<'a, str>),
+    Binary {
+        content_type: Cow<'a, str>,
+        data: String,
+    },
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let encoded = match std::str::from_utf8(&self.data) {
+            Ok(text) if self.content_type == TEXT_CONTENT_TYPE => Encoded::Text(text.into()),
+            _ => Encoded::Binary {
+                content_type: self.content_type.as_str().into(),
+                data: BASE64_STANDARD.encode(&self.data),
+            },
+        };
+        encoded.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        match Encoded::deserialize(deserializer)? {
+            Encoded::Text(text) => Ok(Value::text(text.into_owned())),
+            Encoded::Binary { content_type, data } => Ok(Value {
+                content_type: content_type.into_owned(),
+                data: BASE64_STANDARD.decode(data).map_err(D::Error::custom)?,
+            }),
+        }
+    }
+}
+
+/// A place to keep the values of the key-value store.
+///
+/// Reads take `&self` and writes `&mut self`, so that the provider can share a backend behind a
+/// `RwLock` without the backend having to synchronize itself.
+pub trait Storage: Send + Sync {
+    /// Retrieve the value associated with `key` in `keyspace`.
+    fn get(&self, keyspace: &str, key: &str) -> Result<Option<Value>>;
+
+    /// Associate `value` with `key` in `keyspace`. Once this returns, the value survives a restart
+    /// of the provider if the backend is persistent.
+    fn set(&mut self, keyspace: &str, key: String, value: Value) -> Result<()>;
+
+    /// Remove `key` from `keyspace`, returning whether it was present.
+    fn delete(&mut self, keyspace: &str, key: &str) -> Result<bool>;
+
+    /// Count the keys of `keyspace`.
+    fn count(&self, keyspace: &str) -> Result<usize>;
+
+    /// List up to `limit` keys of `keyspace` in ascending order, starting after `after` if given.
+    fn list_keys(&self, keyspace: &str, after: Option<&str>, limit: usize) -> Result<Vec<String>>;
+
+    /// Remove every key of `keyspace`.
+    fn clear(&mut self, keyspace: &str) -> Result<()>;
+
+    /// Persist the current state in its most compact form, e.g. before the provider stops.
+    fn checkpoint(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Open the backend selected by `config`.
+pub fn open(config: &StorageConfig) -> Result<Box<dyn Storage>> {
+    match config {
+        StorageConfig::Memory => Ok(Box::new(MemoryStorage::default())),
+        StorageConfig::Disk {
+            data_dir,
+            snapshot_interval,
+        } => Ok(Box::new(DiskStorage::open(data_dir, *snapshot_interval)?)),
+    }
+}
+
+/// Values of every keyspace, by keyspace and key. Keys are ordered, so they can be listed in pages.
+type Keyspaces = HashMap<String, BTreeMap<String, Value>>;
+
+/// Values kept in memory only.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    keyspaces: Keyspaces,
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, keyspace: &str, key: &str) -> Result<Option<Value>> {
+        Ok(self
+            .keyspaces
+            .get(keyspace)
+            .and_then(|values| values.get(key))
+            .cloned())
+    }
+
+    fn set(&mut self, keyspace: &str, key: String, value: Value) -> Result<()> {
+        self.keyspaces
+            .entry(keyspace.to_owned())
+            .or_default()
+            .insert(key, value);
+        Ok(())
+    }
+
+    fn delete(&mut self, keyspace: &str, key: &str) -> Result<bool> {
+        let Some(values) = self.keyspaces.get_mut(keyspace) else {
+            return Ok(false);
+        };
+        let deleted = values.remove(key).is_some();
+        if values.is_empty() {
+            self.keyspaces.remove(keyspace);
+        }
+        Ok(deleted)
+    }
+
+    fn count(&self, keyspace: &str) -> Result<usize> {
+        Ok(self.keyspaces.get(keyspace).map_or(0, BTreeMap::len))
+    }
+
+    fn list_keys(&self, keyspace: &str, after: Option<&str>, limit: usize) -> Result<Vec<String>> {
+        let Some(values) = self.keyspaces.get(keyspace) else {
+            return Ok(Vec::new());
+        };
+        let start = match after {
+            Some(after) => Bound::Excluded(after),
+            None => Bound::Unbounded,
+        };
+        Ok(values
+            .range::<str, _>((start, Bound::Unbounded))
+            .take(limit)
+            .map(|(key, _)| key.clone())
+            .collect())
+    }
+
+    fn clear(&mut self, keyspace: &str) -> Result<()> {
+        self.keyspaces.remove(keyspace);
+        Ok(())
+    }
+}
+
+/// A change recorded in the write-ahead log, one JSON document per line.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum Record {
+    Set {
+        keyspace: String,
+        key: String,
+        value: Value,
+    },
+    Delete {
+        keyspace: String,
+        key: String,
+    },
+    Clear {
+        keyspace: String,
+    },
+}
+
+impl Record {
+    fn apply(self, storage: &mut MemoryStorage) -> Result<()> {
+        match self {
+            Record::Set {
+                keyspace,
+                key,
+                value,
+            } => storage.set(&keyspace, key, value),
+            Record::Delete { keyspace, key } => storage.delete(&keyspace, &key).map(|_| ()),
+            Record::Clear { keyspace } => storage.clear(&keyspace),
+        }
+    }
+}
+
+/// Values kept in memory and persisted to a write-ahead log and snapshot files.
+#[derive(Debug)]
+pub struct DiskStorage {
+    data_dir: PathBuf,
+    values: MemoryStorage,
+    /// The write-ahead log, opened for appending.
+    wal: File,
+    /// Number of records in the write-ahead log.
+    wal_records: usize,
+    snapshot_interval: usize,
+}
+
+impl DiskStorage {
+    /// Open the storage in `data_dir`, creating the directory if needed, and recover the values
+    /// written before.
+    pub fn open(data_dir: &Path, snapshot_interval: usize) -> Result<DiskStorage> {
+        fs::create_dir_all(data_dir)
+            .with_context(|| format!("failed to create data directory {}", data_dir.display()))?;
+        let mut values = MemoryStorage::default();
+        let snapshot = data_dir.join(SNAPSHOT_FILE);
+        if snapshot.exists() {
+            let bytes = fs::read(&snapshot)
+                .with_context(|| format!("failed to read {}", snapshot.display()))?;
+            values.keyspaces = serde_json::from_slice(&bytes)
+                .with_context(|| format!("failed to decode {}", snapshot.display()))?;
+        }
+        let wal_path = data_dir.join(WAL_FILE);
+        let wal_records = replay(&wal_path, &mut values)?;
+        let wal = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&wal_path)
+            .with_context(|| format!("failed to open {}", wal_path.display()))?;
+        info!(
+            data_dir = %data_dir.display(),
+            keyspaces = values.keyspaces.len(),
+            wal_records,
+            "opened disk storage"
+        );
+        Ok(DiskStorage {
+            data_dir: data_dir.to_owned(),
+            values,
+            wal,
+            wal_records,
+            snapshot_interval,
+        })
+    }
+
+    /// Append `record` to the write-ahead log and wait until it is on disk.
+    ///
+    /// If that fails, the log is cut back to its previous length: later records would otherwise
+    /// follow a partial line, which makes the log unreadable, and a record which made it to disk
+    /// would be replayed although the write was reported as failed.
+    fn log(&mut self, record: &Record) -> Result<()> {
+        let mut line = serde_json::to_vec(record).context("failed to encode log record")?;
+        line.push(b'\n');
+        let len = self
+            .wal
+            .metadata()
+            .context("failed to read the length of the write-ahead log")?
+            .len();
+        if let Err(e) = self
+            .wal
+            .write_all(&line)
+            .and_then(|()| self.wal.sync_data())
+        {
+            // the log is opened for appending, so the next write starts at the new end
+            if let Err(truncate) = self.wal.set_len(len).and_then(|()| self.wal.sync_data()) {
+                error!("failed to roll back the write-ahead log: {truncate}");
+            }
+            return Err(e).context("failed to append to the write-ahead log");
+        }
+        self.wal_records += 1;
+        Ok(())
+    }
+
+    /// Log `record`, then apply it to the values in memory.
+    fn write(&mut self, record: Record) -> Result<()> {
+        self.log(&record)?;
+        record.apply(&mut self.values)?;
+        if self.wal_records >= self.snapshot_interval {
+            // the write is already durable in the log, which stays valid until replaced
+            if let Err(e) = self.snapshot() {
+                warn!("failed to write snapshot: {e:#}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Write all values to a new snapshot and empty the write-ahead log.
+    ///
+    /// The snapshot is written to a temporary file and renamed over the previous one, so a crash
+    /// leaves either the old snapshot and the full log, or the new snapshot.
+    fn snapshot(&mut self) -> Result<()> {
+        let path = self.data_dir.join(SNAPSHOT_FILE);
+        let temporary = path.with_extension("json.tmp");
+        let file = File::create(&temporary)
+            .with_context(|| format!("failed to create {}", temporary.display()))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer(&mut writer, &self.values.keyspaces)
+            .context("failed to encode snapshot")?;
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())
+            .and_then(|file| file.sync_all())
+            .with_context(|| format!("failed to write {}", temporary.display()))?;
+        fs::rename(&temporary, &path)
+            .and_then(|()| File::open(&self.data_dir)?.sync_all())
+            .with_context(|| format!("failed to replace {}", path.display()))?;
+        self.wal
+            .set_len(0)
+            .and_then(|()| self.wal.sync_all())
+            .context("failed to truncate the write-ahead log")?;
+        self.wal_records = 0;
+        Ok(())
+    }
+}
+
+impl Storage for DiskStorage {
+    fn get(&self, keyspace: &str, key: &str) -> Result<Option<Value>> {
+        self.values.get(keyspace, key)
+    }
+
+    fn set(&mut self, keyspace: &str, key: String, value: Value) -> Result<()> {
+        self.write(Record::Set {
+            keyspace: keyspace.to_owned(),
+            key,
+            value,
+        })
+    }
+
+    fn delete(&mut self, keyspace: &str, key: &str) -> Result<bool> {
+        // only log deletions that change something, so that probing for keys does not grow the log
+        if self.values.get(keyspace, key)?.is_none() {
+            return Ok(false);
+        }
+        self.write(Record::Delete {
+            keyspace: keyspace.to_owned(),
+            key: key.to_owned(),
+        })?;
+        Ok(true)
+    }
+
+    fn count(&self, keyspace: &str) -> Result<usize> {
+        self.values.count(keyspace)
+    }
+
+    fn list_keys(&self, keyspace: &str, after: Option<&str>, limit: usize) -> Result<Vec<String>> {
+        self.values.list_keys(keyspace, after, limit)
+    }
+
+    fn clear(&mut self, keyspace: &str) -> Result<()> {
+        self.write(Record::Clear {
+            keyspace: keyspace.to_owned(),
+        })
+    }
+
+    fn checkpoint(&mut self) -> Result<()> {
+        if self.wal_records > 0 {
+            self.snapshot()?;
+        }
+        Ok(())
+    }
+}
+
+/// Apply the records of the write-ahead log at `path` to `values`, returning their number.
+///
+/// A crash while appending can leave a partial last line, which is dropped from the log. Any other
+/// malformed record is an error, as skipping it would silently lose writes.
+fn replay(path: &Path, values: &mut MemoryStorage) -> Result<usize> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let mut records = 0;
+    let mut offset = 0;
+    for line in contents.split_inclusive(|byte| *byte == b'\n') {
+        if !line.ends_with(b"\n") {
+            warn!(
+                path = %path.display(),
+                "dropping a partial record at the end of the write-ahead log"
+            );
+            OpenOptions::new()
+                .write(true)
+                .open(path)
+                .and_then(|file| file.set_len(offset as u64))
+                .with_context(|| format!("failed to truncate {}", path.display()))?;
+            break;
+        }
+        match serde_json::from_slice::<Record>(line) {
+            Ok(record) => record.apply(values)?,
+            Err(e) => bail!(
+                "malformed record at byte {offset} of {}: {e}",
+                path.display()
+            ),
+        }
+        records += 1;
+        offset += line.len();
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty data directory for the test `name`.
+    fn data_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("key-value-provider-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn text(storage: &DiskStorage, key: &str) -> Option<String> {
+        let value = storage.get("space", key).unwrap()?;
+        Some(String::from_utf8(value.data).unwrap())
+    }
+
+    #[test]
+    fn reopen_replays_the_log() {
+        let dir = data_dir("replay");
+        let mut storage = DiskStorage::open(&dir, 100).unwrap();
+        storage
+            .set("space", "a".into(), Value::text("1".into()))
+            .unwrap();
+        storage
+            .set("space", "b".into(), Value::text("2".into()))
+            .unwrap();
+        storage
+            .set("other", "c".into(), Value::text("3".into()))
+            .unwrap();
+        assert!(storage.delete("space", "a").unwrap());
+        assert!(!storage.delete("space", "a").unwrap());
+        storage.clear("other").unwrap();
+        drop(storage);
+
+        let storage = DiskStorage::open(&dir, 100).unwrap();
+        assert_eq!(storage.wal_records, 5);
+        assert_eq!(text(&storage, "a"), None);
+        assert_eq!(text(&storage, "b").as_deref(), Some("2"));
+        assert_eq!(storage.count("other").unwrap(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reopen_drops_a_partial_last_record() {
+        let dir = data_dir("partial");
+        let mut storage = DiskStorage::open(&dir, 100).unwrap();
+        storage
+            .set("space", "a".into(), Value::text("1".into()))
+            .unwrap();
+        drop(storage);
+        let wal = dir.join(WAL_FILE);
+        let len = fs::metadata(&wal).unwrap().len();
+        let mut file = OpenOptions::new().append(true).open(&wal).unwrap();
+        file.write_all(br#"{"op":"set","keyspace":"space""#)
+            .unwrap();
+        drop(file);
+
+        let mut storage = DiskStorage::open(&dir, 100).unwrap();
+        assert_eq!(fs::metadata(&wal).unwrap().len(), len);
+        assert_eq!(text(&storage, "a").as_deref(), Some("1"));
+        storage
+            .set("space", "b".into(), Value::text("2".into()))
+            .unwrap();
+        drop(storage);
+
+        let storage = DiskStorage::open(&dir, 100).unwrap();
+        assert_eq!(storage.wal_records, 2);
+        assert_eq!(text(&storage, "b").as_deref(), Some("2"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reopen_fails_on_a_malformed_record() {
+        let dir = data_dir("malformed");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(WAL_FILE), b"not json\n").unwrap();
+        assert!(DiskStorage::open(&dir, 100).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn snapshot_compacts_the_log() {
+        let dir = data_dir("snapshot");
+        let mut storage = DiskStorage::open(&dir, 2).unwrap();
+        storage
+            .set("space", "a".into(), Value::text("1".into()))
+            .unwrap();
+        storage
+            .set("space", "a".into(), Value::text("2".into()))
+            .unwrap();
+        assert_eq!(storage.wal_records, 0);
+        assert_eq!(fs::metadata(dir.join(WAL_FILE)).unwrap().len(), 0);
+        let binary = Value {
+            content_type: "application/octet-stream".into(),
+            data: vec![0, 159, 255],
+        };
+        storage.set("space", "b".into(), binary.clone()).unwrap();
+        drop(storage);
+
+        let mut storage = DiskStorage::open(&dir, 2).unwrap();
+        assert_eq!(storage.wal_records, 1);
+        assert_eq!(text(&storage, "a").as_deref(), Some("2"));
+        assert_eq!(storage.get("space", "b").unwrap(), Some(binary.clone()));
+        storage.checkpoint().unwrap();
+        drop(storage);
+
+        let storage = DiskStorage::open(&dir, 2).unwrap();
+        assert_eq!(storage.wal_records, 0);
+        assert_eq!(storage.get("space", "b").unwrap(), Some(binary));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn text_values_are_logged_as_strings() {
+        let encoded = serde_json::to_string(&Value::text("hello".into())).unwrap();
+        assert_eq!(encoded, r#""hello""#);
+        let decoded: Value = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded, Value::text("hello".into()));
+    }
+}
@@ -0,0 +1,29 @@
+//! Runtime configuration of the component, provided through `wasi:config`.
+//!
+//! | Key          | Default | Description                                            |
+//! | ------------ | ------- | ------------------------------------------------------ |
+//! | `bucket`     | (empty) | Name of the key/value bucket holding the bookings      |
+//! | `key-prefix` | (empty) | Prefix of every key, e.g. `booking:`                   |
+
+use crate::exports::demo::booking_master::booking_management::BookingError;
+use crate::wasi::config::store;
+
+pub struct Config {
+    pub bucket: String,
+    pub key_prefix: String,
+}
+
+impl Config {
+    /// Read the configuration, falling back to the defaults for missing values.
+    pub fn load() -> Result<Config, BookingError> {
+        Ok(Config {
+            bucket: get("bucket")?.unwrap_or_default(),
+            key_prefix: get("key-prefix")?.unwrap_or_default(),
+        })
+    }
+}
+
+fn get(key: &str) -> Result<Option<String>, BookingError> {
+    store::get(key)
+        .map_err(|e| BookingError::Storage(format!("failed to read configuration {key}: {e:?}")))
+}
@@ -0,0 +1,172 @@
+//! History of the changes made to every booking.
+//!
+//! Every change to a booking is recorded under a key of its own, next to the outbox event of the
+//! change and in the same encoding. Entries are never updated, and only removed again when the
+//! deletion they record fails, so the history keeps the contents of deleted bookings and allows
+//! restoring them.
+//!
+//! The keys of the entries of a booking are listed in an index of its own, updated in the same
+//! batch write as the entries, so that reading a history does not list every key of the bucket.
+//! Entries lost to concurrent changes of the same booking (see [`Storage`]) and entries recorded
+//! before the indexes existed are added by `reconcile-indexes`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::exports::demo::booking_master::booking_management::{
+    BookingError, HistoryEntry, OutboxEvent,
+};
+use crate::keys::Keys;
+use crate::outbox;
+use crate::wasi::keyvalue::batch;
+use crate::{storage_error, Storage, CHUNK_SIZE};
+
+/// Key under which the history entry of `event` is stored.
+pub fn key(keys: &Keys, event: &OutboxEvent) -> String {
+    keys.history(event.booking_id, &event.key)
+}
+
+/// Serialize the history entry of `event` into the bytes stored in the bucket.
+pub fn encode(event: &OutboxEvent) -> Result<Vec<u8>, BookingError> {
+    outbox::encode(event)
+}
+
+/// Deserialize the history entry stored under `key`.
+pub fn decode(key: String, bytes: &[u8]) -> Result<HistoryEntry, BookingError> {
+    let event = outbox::decode(key, bytes)?;
+    Ok(HistoryEntry {
+        kind: event.kind,
+        booking: event.booking,
+        occurred_at: event.occurred_at,
+    })
+}
+
+/// Read the indexes of the bookings with the given IDs, in the order of `ids`.
+fn read_indexes(storage: &Storage, ids: &[u32]) -> Result<Vec<BTreeSet<String>>, BookingError> {
+    let keys: Vec<String> = ids
+        .iter()
+        .map(|id| storage.keys.history_index(*id))
+        .collect();
+    let mut found: HashMap<String, Vec<u8>> = HashMap::new();
+    for chunk in keys.chunks(CHUNK_SIZE) {
+        found.extend(
+            batch::get_many(&storage.bucket, chunk)
+                .map_err(storage_error("read history index"))?
+                .into_iter()
+                .flatten(),
+        );
+    }
+    ids.iter()
+        .zip(&keys)
+        .map(|(id, key)| match found.remove(key) {
+            None => Ok(BTreeSet::new()),
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+                BookingError::Storage(format!("failed to decode history index of {id}: {e}"))
+            }),
+        })
+        .collect()
+}
+
+/// Serialize the index of the booking with ID `id` into the key/value pair to store.
+fn encode_index(
+    storage: &Storage,
+    id: u32,
+    events: &BTreeSet<String>,
+) -> Result<(String, Vec<u8>), BookingError> {
+    let bytes = serde_json::to_vec(events).map_err(|e| {
+        BookingError::Storage(format!("failed to encode history index of {id}: {e}"))
+    })?;
+    Ok((storage.keys.history_index(id), bytes))
+}
+
+/// Read the history of the booking with ID `id`, oldest first.
+pub fn read(storage: &Storage, id: u32) -> Result<Vec<HistoryEntry>, BookingError> {
+    let Some(events) = read_indexes(storage, &[id])?.pop() else {
+        return Ok(Vec::new());
+    };
+    let keys: Vec<String> = events
+        .iter()
+        .map(|event| storage.keys.history(id, event))
+        .collect();
+    let mut found: HashMap<String, Vec<u8>> = HashMap::new();
+    for chunk in keys.chunks(CHUNK_SIZE) {
+        found.extend(
+            batch::get_many(&storage.bucket, chunk)
+                .map_err(storage_error("read history"))?
+                .into_iter()
+                .flatten(),
+        );
+    }
+    // entries withdrawn because their change failed are still listed
+    events
+        .into_iter()
+        .zip(&keys)
+        .filter_map(|(event, key)| found.remove(key).map(|bytes| decode(event, &bytes)))
+        .collect()
+}
+
+/// Entries recorded by a set of changes, listed in the indexes of their bookings in the same batch
+/// write as the entries themselves.
+pub struct Index<'a> {
+    storage: &'a Storage,
+    added: BTreeMap<u32, BTreeSet<String>>,
+}
+
+impl<'a> Index<'a> {
+    pub fn new(storage: &'a Storage) -> Self {
+        Index {
+            storage,
+            added: BTreeMap::new(),
+        }
+    }
+
+    /// List the entry of the event with the given key in the history of the booking with ID `id`.
+    pub fn add(&mut self, id: u32, event: &str) {
+        self.added.entry(id).or_default().insert(event.to_string());
+    }
+
+    /// Serialize the index of every booking with added entries into the key/value pairs to store.
+    pub fn encode(self) -> Result<Vec<(String, Vec<u8>)>, BookingError> {
+        let mut entries = Vec::new();
+        merge(self.storage, self.added, &mut entries)?;
+        Ok(entries)
+    }
+}
+
+/// Add `events` to the indexes of their bookings, appending the key/value pairs to store for the
+/// indexes which changed to `entries`. Returns the number of events which were not listed yet.
+fn merge(
+    storage: &Storage,
+    events: BTreeMap<u32, BTreeSet<String>>,
+    entries: &mut Vec<(String, Vec<u8>)>,
+) -> Result<usize, BookingError> {
+    let ids: Vec<u32> = events.keys().copied().collect();
+    let mut missing = 0;
+    for ((id, added), mut listed) in events.into_iter().zip(read_indexes(storage, &ids)?) {
+        let before = listed.len();
+        listed.extend(added);
+        if listed.len() > before {
+            missing += listed.len() - before;
+            entries.push(encode_index(storage, id, &listed)?);
+        }
+    }
+    Ok(missing)
+}
+
+/// Add the entries among `keys` which are missing from the indexes of their bookings, returning
+/// their number. Entries of other tenants are ignored.
+pub fn reconcile(storage: &Storage, keys: &[String]) -> Result<usize, BookingError> {
+    let mut events: BTreeMap<u32, BTreeSet<String>> = BTreeMap::new();
+    for key in keys {
+        if let Some((tenant, id, event)) = storage.keys.parse_any_history(key) {
+            if tenant == storage.tenant {
+                events.entry(id).or_default().insert(event.to_string());
+            }
+        }
+    }
+    let mut entries = Vec::new();
+    let missing = merge(storage, events, &mut entries)?;
+    for chunk in entries.chunks(CHUNK_SIZE) {
+        batch::set_many(&storage.bucket, chunk).map_err(storage_error("store history index"))?;
+    }
+    Ok(missing)
+}
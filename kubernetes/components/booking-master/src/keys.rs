@@ -0,0 +1,213 @@
+//! Layout of the keys used in the bucket.
+//!
+//! Every key starts with the configured key prefix, so that several deployments can share a
+//! bucket. The keys of a tenant's bookings and of everything recorded about them, such as their
+//! history or the indexes, additionally start with `tenant:<tenant>:`, except for the default
+//! tenant. With an empty prefix, bookings of the default tenant are thus stored under their bare
+//! ID, as before keys were prefixed. The outbox is shared by all tenants.
+
+/// Prefix of the keys scoped to a tenant other than the default tenant.
+const TENANT_PREFIX: &str = "tenant:";
+/// Prefix of the keys holding archived bookings.
+const ARCHIVE_PREFIX: &str = "archive:";
+/// Prefix of the keys holding events waiting in the outbox.
+const OUTBOX_PREFIX: &str = "outbox:";
+/// Prefix of the keys listing the events waiting in the outbox.
+const OUTBOX_SLOT_PREFIX: &str = "outbox-slot:";
+/// Prefix of the keys holding the history entries of a booking.
+const HISTORY_PREFIX: &str = "history:";
+/// Prefix of the keys holding the reservations of a resource.
+const RESOURCE_PREFIX: &str = "resource:";
+/// Prefix of the keys holding the bookings containing a token.
+const SEARCH_PREFIX: &str = "search:";
+
+/// Keys of a tenant in a deployment.
+pub struct Keys {
+    /// Prefix of all keys of the deployment.
+    prefix: String,
+    /// Prefix of the keys of the tenant.
+    scope: String,
+}
+
+impl Keys {
+    /// Keys of `tenant`, which must be a valid tenant name.
+    pub fn new(prefix: String, tenant: &str) -> Self {
+        let scope = if tenant.is_empty() {
+            prefix.clone()
+        } else {
+            format!("{prefix}{TENANT_PREFIX}{tenant}:")
+        };
+        Keys { prefix, scope }
+    }
+
+    /// Key under which the booking with the given ID is stored.
+    pub fn booking(&self, id: u32) -> String {
+        format!("{}{id}", self.scope)
+    }
+
+    /// Extract the booking ID from a key, if the key holds a booking.
+    pub fn parse_booking(&self, key: &str) -> Option<u32> {
+        parse_id(key.strip_prefix(&self.scope)?)
+    }
+
+    /// Split a key of any tenant into the tenant and the rest of the key following its scope.
+    fn split_tenant<'a>(&self, key: &'a str) -> Option<(&'a str, &'a str)> {
+        let key = key.strip_prefix(&self.prefix)?;
+        match key.strip_prefix(TENANT_PREFIX) {
+            Some(scoped) => scoped.split_once(':'),
+            None => Some(("", key)),
+        }
+    }
+
+    /// Extract the tenant and the booking ID from a key, if the key holds a booking of any tenant.
+    pub fn parse_any_booking<'a>(&self, key: &'a str) -> Option<(&'a str, u32)> {
+        let (tenant, id) = self.split_tenant(key)?;
+        parse_id(id).map(|id| (tenant, id))
+    }
+
+    /// Key under which the booking with the given ID is archived once it expired.
+    pub fn archive(&self, id: u32) -> String {
+        format!("{}{ARCHIVE_PREFIX}{id}", self.scope)
+    }
+
+    /// Key under which the IDs of the bookings of the tenant are listed.
+    pub fn booking_index(&self) -> String {
+        format!("{}booking-index", self.scope)
+    }
+
+    /// Key marking that the bookings stored before the booking index existed have been listed.
+    pub fn booking_index_backfilled(&self) -> String {
+        format!("{}booking-index-backfilled", self.scope)
+    }
+
+    /// Key of the counter booking IDs are allocated from.
+    pub fn sequence(&self) -> String {
+        format!("{}sequence:booking", self.scope)
+    }
+
+    /// Key under which the outbox event with the given key is stored.
+    pub fn outbox(&self, event: &str) -> String {
+        format!("{}{OUTBOX_PREFIX}{event}", self.prefix)
+    }
+
+    /// Extract the event key from a key, if the key holds an outbox event.
+    pub fn parse_outbox<'a>(&self, key: &'a str) -> Option<&'a str> {
+        key.strip_prefix(&self.prefix)?.strip_prefix(OUTBOX_PREFIX)
+    }
+
+    /// Key of the counter the slots of the outbox index are allocated from.
+    pub fn outbox_sequence(&self) -> String {
+        format!("{}sequence:outbox", self.prefix)
+    }
+
+    /// Key of the slot of the outbox index with the given number.
+    pub fn outbox_slot(&self, slot: u64) -> String {
+        format!("{}{OUTBOX_SLOT_PREFIX}{slot}", self.prefix)
+    }
+
+    /// Extract the slot number from a key, if the key is a slot of the outbox index.
+    pub fn parse_outbox_slot(&self, key: &str) -> Option<u64> {
+        key.strip_prefix(&self.prefix)?
+            .strip_prefix(OUTBOX_SLOT_PREFIX)?
+            .parse()
+            .ok()
+    }
+
+    /// Key under which the number of the last slot of the outbox index known to be done is stored.
+    pub fn outbox_head(&self) -> String {
+        format!("{}outbox-head", self.prefix)
+    }
+
+    /// Key under which the history entry of the event with the given key is stored.
+    pub fn history(&self, id: u32, event: &str) -> String {
+        format!("{}{HISTORY_PREFIX}{id}:{event}", self.scope)
+    }
+
+    /// Extract the tenant, the booking ID and the event key from a key, if the key holds a history
+    /// entry of any tenant.
+    pub fn parse_any_history<'a>(&self, key: &'a str) -> Option<(&'a str, u32, &'a str)> {
+        let (tenant, key) = self.split_tenant(key)?;
+        let (id, event) = key.strip_prefix(HISTORY_PREFIX)?.split_once(':')?;
+        parse_id(id).map(|id| (tenant, id, event))
+    }
+
+    /// Key under which the keys of the events in the history of the booking with the given ID are
+    /// listed.
+    pub fn history_index(&self, id: u32) -> String {
+        format!("{}history-index:{id}", self.scope)
+    }
+
+    /// Key under which the reservations of the given resource are stored.
+    pub fn resource(&self, name: &str) -> String {
+        format!("{}{RESOURCE_PREFIX}{name}", self.scope)
+    }
+
+    /// Key under which the IDs of the bookings containing the given search token are stored.
+    pub fn search(&self, token: &str) -> String {
+        format!("{}{SEARCH_PREFIX}{token}", self.scope)
+    }
+
+    /// Key marking that the bookings stored before the search index existed have been indexed.
+    pub fn search_backfilled(&self) -> String {
+        format!("{}search-backfilled", self.scope)
+    }
+}
+
+/// Parse a booking ID written the way [`Keys::booking`] writes it.
+fn parse_id(id: &str) -> Option<u32> {
+    id.parse()
+        .ok()
+        .filter(|parsed: &u32| parsed.to_string() == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_tenant_keys_are_not_scoped() {
+        let keys = Keys::new(String::new(), "");
+        assert_eq!(keys.booking(42), "42");
+        assert_eq!(keys.history(42, "e1"), "history:42:e1");
+        assert_eq!(keys.parse_booking("42"), Some(42));
+        assert_eq!(keys.parse_booking("042"), None);
+        assert_eq!(keys.parse_booking("history:42:e1"), None);
+    }
+
+    #[test]
+    fn tenant_keys_follow_the_prefix() {
+        let keys = Keys::new("demo:".to_string(), "team-a");
+        assert_eq!(keys.booking(42), "demo:tenant:team-a:42");
+        assert_eq!(keys.resource("room"), "demo:tenant:team-a:resource:room");
+        assert_eq!(keys.search("room"), "demo:tenant:team-a:search:room");
+        assert_eq!(keys.booking_index(), "demo:tenant:team-a:booking-index");
+        assert_eq!(keys.outbox("e1"), "demo:outbox:e1");
+        assert_eq!(keys.parse_booking("demo:tenant:team-a:42"), Some(42));
+        assert_eq!(keys.parse_booking("demo:42"), None);
+        assert_eq!(keys.outbox_slot(7), "demo:outbox-slot:7");
+        assert_eq!(keys.parse_outbox_slot("demo:outbox-slot:7"), Some(7));
+        assert_eq!(keys.parse_outbox_slot("demo:outbox:e1"), None);
+        assert_eq!(keys.parse_outbox("demo:outbox:e1"), Some("e1"));
+        assert_eq!(keys.parse_outbox("demo:outbox-slot:7"), None);
+        assert_eq!(keys.parse_outbox("demo:outbox-head"), None);
+        assert_eq!(keys.parse_any_booking("demo:sequence:outbox"), None);
+    }
+
+    #[test]
+    fn keys_of_any_tenant_are_parsed() {
+        let keys = Keys::new("demo:".to_string(), "");
+        assert_eq!(keys.parse_any_booking("demo:42"), Some(("", 42)));
+        assert_eq!(
+            keys.parse_any_booking("demo:tenant:team-a:42"),
+            Some(("team-a", 42))
+        );
+        assert_eq!(keys.parse_any_booking("demo:sequence:booking"), None);
+        assert_eq!(keys.parse_any_booking("demo:booking-index"), None);
+        assert_eq!(keys.parse_any_booking("other:42"), None);
+        assert_eq!(
+            keys.parse_any_history("demo:tenant:team-a:history:42:e1"),
+            Some(("team-a", 42, "e1"))
+        );
+        assert_eq!(keys.parse_any_history("demo:history-index:42"), None);
+    }
+}
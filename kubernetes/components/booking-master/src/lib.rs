@@ -1,58 +1,857 @@
 wit_bindgen::generate!({ generate_all });
 
-use exports::demo::booking_master::booking_management::Guest;
-use wasi::keyvalue::store;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+mod config;
+mod history;
+mod keys;
+mod listing;
+mod outbox;
+mod record;
+mod reservation;
+mod search;
+#[cfg(test)]
+mod testing;
+mod validation;
+
+use exports::demo::booking_master::booking_management::{
+    Booking, BookingEntry, BookingError, BookingPage, EventKind, Guest, HistoryEntry, OutboxEvent,
+};
+use wasi::clocks::wall_clock;
+use wasi::keyvalue::{atomics, batch, store};
 use wasi::logging::logging;
 
+use crate::config::Config;
+use crate::keys::Keys;
+
 struct Component {}
 
-const LOG_CONTEXT: &'static str = "demo.booking-master";
+/// Maximum number of keys read or written with a single batch operation.
+const CHUNK_SIZE: usize = 500;
+
+/// The bucket holding the bookings of a tenant, together with the layout of its keys.
+///
+/// The key/value store offers no compare-and-swap, so a value read and written back, be it a
+/// booking or an entry of a secondary index, can overwrite a change written concurrently in
+/// between. Updates of a booking are only checked against its current version, and two bookings of
+/// the same resource written concurrently can both pass the overlap check. Index entries losing a
+/// change this way are repaired by `reconcile-indexes`, which lists every key of the bucket.
+pub struct Storage {
+    bucket: store::Bucket,
+    tenant: String,
+    keys: Keys,
+}
+
+/// The secondary indexes of a storage, updated in memory so that a set of changes can be stored in
+/// a single batch write.
+struct Indexes<'a> {
+    reservations: reservation::Index<'a>,
+    search: search::Index<'a>,
+    listing: listing::Index<'a>,
+    outbox: outbox::Index<'a>,
+    history: history::Index<'a>,
+}
+
+impl<'a> Indexes<'a> {
+    fn new(storage: &'a Storage) -> Self {
+        Indexes {
+            reservations: reservation::Index::new(storage),
+            search: search::Index::new(storage),
+            listing: listing::Index::new(storage),
+            outbox: outbox::Index::new(storage),
+            history: history::Index::new(storage),
+        }
+    }
+
+    /// Record that the event with the given key was recorded for the booking with ID `id`.
+    fn recorded(&mut self, id: u32, event: &str) {
+        self.outbox.add(event);
+        self.history.add(id, event);
+    }
+
+    /// Record that the booking with ID `id` changes from `previous` to `next`, leaving the indexes
+    /// unchanged if it fails.
+    ///
+    /// Fails with [`BookingError::Overlap`] if `next` reserves its resource at a time already
+    /// reserved by another booking.
+    fn update(
+        &mut self,
+        id: u32,
+        previous: Option<&Booking>,
+        next: Option<&Booking>,
+    ) -> Result<(), BookingError> {
+        let change = self.search.prepare(previous, next)?;
+        let listed = self.listing.prepare(previous, next)?;
+        self.reservations.update(id, previous, next)?;
+        self.search.apply(id, change);
+        self.listing.apply(id, listed);
+        Ok(())
+    }
+
+    /// Keys of the index entries left empty by the changes, to delete once the changes are stored.
+    fn emptied(&self) -> Vec<String> {
+        self.search.emptied()
+    }
+
+    /// Serialize the changed entries of every index into the key/value pairs to store.
+    fn encode(self) -> Result<Vec<(String, Vec<u8>)>, BookingError> {
+        let mut entries = self.reservations.encode()?;
+        entries.extend(self.search.encode()?);
+        entries.extend(self.listing.encode()?);
+        entries.extend(self.outbox.encode()?);
+        entries.extend(self.history.encode()?);
+        Ok(entries)
+    }
+}
+
+const LOG_CONTEXT: &str = "demo.booking-master";
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 500;
+const DEFAULT_EVENT_BATCH: u32 = 100;
+const DEFAULT_SWEEP_BATCH: u32 = 100;
+/// Number of IDs tried before giving up on finding one not taken by a client-chosen ID.
+const MAX_ID_ATTEMPTS: u32 = 16;
+
+/// Wrap a key/value store error into a [`BookingError::Storage`] describing the failed action.
+fn storage_error(action: &'static str) -> impl Fn(store::Error) -> BookingError {
+    move |e| BookingError::Storage(format!("failed to {action}: {e:?}"))
+}
 
 impl Component {
-    fn bucket() -> Result<store::Bucket, _rt::String> {
-        // Redis does not support bucket names
-        store::open("").map_err(|e| format!("failed to open bucket: {e:?}"))
+    /// Open the configured bucket for `tenant`.
+    ///
+    /// Redis does not support bucket names, so deployments sharing a Redis instance are kept apart
+    /// by their key prefix instead.
+    fn storage(tenant: &str) -> Result<Storage, BookingError> {
+        validation::tenant(tenant)?;
+        let config = Config::load()?;
+        Ok(Storage {
+            bucket: store::open(&config.bucket).map_err(storage_error("open bucket"))?,
+            tenant: tenant.to_string(),
+            keys: Keys::new(config.key_prefix, tenant),
+        })
+    }
+
+    /// Read the booking stored under `id`, if any.
+    fn read(storage: &Storage, id: u32) -> Result<Option<Booking>, BookingError> {
+        storage
+            .bucket
+            .get(&storage.keys.booking(id))
+            .map_err(storage_error("read booking"))?
+            .map(record::decode)
+            .transpose()
+    }
+
+    /// Read the values stored under the given booking IDs, in the order of `ids`.
+    fn read_many(storage: &Storage, ids: &[u32]) -> Result<Vec<Option<Vec<u8>>>, BookingError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let keys: Vec<String> = ids.iter().map(|id| storage.keys.booking(*id)).collect();
+        let found: HashMap<String, Vec<u8>> = batch::get_many(&storage.bucket, &keys)
+            .map_err(storage_error("read bookings"))?
+            .into_iter()
+            .flatten()
+            .collect();
+        Ok(keys.iter().map(|key| found.get(key).cloned()).collect())
+    }
+
+    fn exists(storage: &Storage, id: u32) -> Result<bool, BookingError> {
+        storage
+            .bucket
+            .exists(&storage.keys.booking(id))
+            .map_err(storage_error("check booking"))
+    }
+
+    /// Allocate an unused booking ID from the sequence kept in the bucket.
+    ///
+    /// Values of the sequence are handed out atomically, so concurrent callers never receive the
+    /// same ID. IDs chosen by clients through `add-booking` are skipped.
+    fn allocate_id(storage: &Storage) -> Result<u32, BookingError> {
+        for _ in 0..MAX_ID_ATTEMPTS {
+            let next = atomics::increment(&storage.bucket, &storage.keys.sequence(), 1)
+                .map_err(storage_error("allocate booking ID"))?;
+            let id = u32::try_from(next)
+                .map_err(|_| BookingError::Storage("booking IDs are exhausted".to_string()))?;
+            if !Self::exists(storage, id)? {
+                return Ok(id);
+            }
+        }
+        Err(BookingError::Storage(format!(
+            "no unused booking ID found in {MAX_ID_ATTEMPTS} attempts"
+        )))
+    }
+
+    /// Store a new booking under `id`, stamping its creation.
+    fn insert(storage: &Storage, id: u32, booking: Booking) -> Result<Booking, BookingError> {
+        let now = wall_clock::now().seconds;
+        let booking = Booking {
+            created_at: now,
+            updated_at: now,
+            version: 1,
+            ..booking
+        };
+        Self::write(storage, EventKind::Created, id, &booking, None)?;
+        Ok(booking)
+    }
+
+    /// Collect all keys in the bucket.
+    ///
+    /// The order in which the store returns keys is unspecified, so callers have to sort the
+    /// result themselves when they need a stable order.
+    fn all_keys(storage: &Storage) -> Result<Vec<String>, BookingError> {
+        let mut all = Vec::new();
+        let mut cursor = None;
+        loop {
+            let response = storage
+                .bucket
+                .list_keys(cursor)
+                .map_err(storage_error("list keys"))?;
+            all.extend(response.keys);
+            match response.cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        Ok(all)
+    }
+
+    /// Group the bookings among `keys`, the keys of all tenants, by tenant. Tenants holding only
+    /// history entries are included without bookings. IDs are sorted in ascending order.
+    fn tenants(storage: &Storage, keys: &[String]) -> BTreeMap<String, Vec<u32>> {
+        let mut tenants: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        for key in keys {
+            if let Some((tenant, id)) = storage.keys.parse_any_booking(key) {
+                tenants.entry(tenant.to_string()).or_default().push(id);
+            } else if let Some((tenant, _, _)) = storage.keys.parse_any_history(key) {
+                tenants.entry(tenant.to_string()).or_default();
+            }
+        }
+        for ids in tenants.values_mut() {
+            ids.sort_unstable();
+            ids.dedup();
+        }
+        tenants
+    }
+
+    /// Collect the IDs of all bookings in the bucket, sorted in ascending order.
+    fn booking_ids(storage: &Storage) -> Result<Vec<u32>, BookingError> {
+        let mut ids: Vec<u32> = Self::all_keys(storage)?
+            .iter()
+            .filter_map(|k| storage.keys.parse_booking(k))
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        Ok(ids)
+    }
+
+    /// Append the entries storing `booking` under `id` to `entries`, together with the outbox
+    /// event and the history entry recording the change. Returns the key of the event, which
+    /// still has to be added to the indexes.
+    fn record(
+        storage: &Storage,
+        kind: EventKind,
+        id: u32,
+        booking: &Booking,
+        entries: &mut Vec<(String, Vec<u8>)>,
+    ) -> Result<String, BookingError> {
+        let event = outbox::event(kind, &storage.tenant, id, booking);
+        entries.push((storage.keys.booking(id), record::encode(booking)?));
+        entries.push((storage.keys.outbox(&event.key), outbox::encode(&event)?));
+        entries.push((
+            history::key(&storage.keys, &event),
+            history::encode(&event)?,
+        ));
+        Ok(event.key)
+    }
+
+    /// Store `booking` under `id` together with the outbox event recording the change and the
+    /// updated indexes, without compare-and-swap (see [`Storage`]).
+    fn write(
+        storage: &Storage,
+        kind: EventKind,
+        id: u32,
+        booking: &Booking,
+        previous: Option<&Booking>,
+    ) -> Result<(), BookingError> {
+        let mut index = Indexes::new(storage);
+        index.update(id, previous, Some(booking))?;
+        let mut entries = Vec::new();
+        let event = Self::record(storage, kind, id, booking, &mut entries)?;
+        index.recorded(id, &event);
+        let emptied = index.emptied();
+        entries.extend(index.encode()?);
+        batch::set_many(&storage.bucket, &entries).map_err(storage_error("store booking"))?;
+        Self::prune(storage, &emptied);
+        Ok(())
+    }
+
+    /// Remove the given bookings from storage and the indexes, recording a `kind` event for
+    /// each in the outbox and their history. Expired bookings are archived as well.
+    ///
+    /// A batch can only set or only delete keys, so the events, the history entries, the archived
+    /// bookings and the updated indexes are recorded first and withdrawn again if the
+    /// bookings cannot be deleted.
+    fn remove(
+        storage: &Storage,
+        kind: EventKind,
+        bookings: &[(u32, Booking)],
+    ) -> Result<(), BookingError> {
+        let mut index = Indexes::new(storage);
+        let mut entries = Vec::new();
+        for (id, booking) in bookings {
+            index.update(*id, Some(booking), None)?;
+            let event = outbox::event(kind, &storage.tenant, *id, booking);
+            entries.push((storage.keys.outbox(&event.key), outbox::encode(&event)?));
+            entries.push((
+                history::key(&storage.keys, &event),
+                history::encode(&event)?,
+            ));
+            index.recorded(*id, &event.key);
+            if let EventKind::Expired = kind {
+                entries.push((storage.keys.archive(*id), record::encode(booking)?));
+            }
+        }
+        let recorded: Vec<String> = entries.iter().map(|(key, _)| key.clone()).collect();
+        let emptied = index.emptied();
+        entries.extend(index.encode()?);
+        batch::set_many(&storage.bucket, &entries).map_err(storage_error("store events"))?;
+        let keys: Vec<String> = bookings
+            .iter()
+            .map(|(id, _)| storage.keys.booking(*id))
+            .collect();
+        if let Err(e) = batch::delete_many(&storage.bucket, &keys) {
+            if let Err(e) = batch::delete_many(&storage.bucket, &recorded) {
+                logging::log(
+                    logging::Level::Error,
+                    LOG_CONTEXT,
+                    &format!("Failed to withdraw events of removed bookings: {:?}", e),
+                );
+            }
+            let mut index = Indexes::new(storage);
+            let restored = bookings
+                .iter()
+                .try_for_each(|(id, booking)| index.update(*id, None, Some(booking)))
+                .and_then(|()| index.encode())
+                .and_then(|entries| {
+                    batch::set_many(&storage.bucket, &entries)
+                        .map_err(storage_error("store indexes"))
+                });
+            if let Err(e) = restored {
+                logging::log(
+                    logging::Level::Error,
+                    LOG_CONTEXT,
+                    &format!("Failed to restore the indexes of removed bookings: {:?}", e),
+                );
+            }
+            return Err(storage_error("delete booking")(e));
+        }
+        Self::prune(storage, &emptied);
+        Ok(())
+    }
+
+    /// Delete the index entries under `keys` left empty by a stored change.
+    ///
+    /// The change is stored already, so failures are only logged: an empty entry reads like a
+    /// missing one.
+    fn prune(storage: &Storage, keys: &[String]) {
+        if let Err(e) = search::prune(storage, keys) {
+            logging::log(
+                logging::Level::Warn,
+                LOG_CONTEXT,
+                &format!("Failed to delete emptied index entries: {:?}", e),
+            );
+        }
+    }
+
+    /// Add the bookings of `storage` with the given IDs to the search index, and mark the bookings
+    /// stored before the index existed as indexed.
+    ///
+    /// Bookings which cannot be decoded are skipped, so that they do not stop the reconciliation.
+    fn backfill_search(storage: &Storage, ids: &[u32]) -> Result<(), BookingError> {
+        let mut index = search::Index::new(storage);
+        for chunk in ids.chunks(MAX_PAGE_SIZE as usize) {
+            // bookings deleted since the keys were listed are missing from the result
+            for (id, value) in chunk.iter().zip(Self::read_many(storage, chunk)?) {
+                let Some(value) = value else {
+                    continue;
+                };
+                match record::decode(value) {
+                    Ok(booking) => {
+                        let change = index.prepare(None, Some(&booking))?;
+                        index.apply(*id, change);
+                    }
+                    Err(e) => logging::log(
+                        logging::Level::Warn,
+                        LOG_CONTEXT,
+                        &format!("Skipping undecodable booking {}: {:?}", id, e),
+                    ),
+                }
+            }
+        }
+        for chunk in index.encode()?.chunks(MAX_PAGE_SIZE as usize) {
+            batch::set_many(&storage.bucket, chunk).map_err(storage_error("store search index"))?;
+        }
+        search::mark_backfilled(storage)
+    }
+
+    /// Collect the bookings of `storage` which expired at `now`, at most `limit`.
+    ///
+    /// Bookings which cannot be decoded are skipped, so that they do not stop the sweep.
+    fn expired(
+        storage: &Storage,
+        ids: &[u32],
+        now: u64,
+        limit: usize,
+    ) -> Result<Vec<(u32, Booking)>, BookingError> {
+        let mut expired = Vec::new();
+        for chunk in ids.chunks(MAX_PAGE_SIZE as usize) {
+            // bookings deleted since the keys were listed are missing from the result
+            for (id, value) in chunk.iter().zip(Self::read_many(storage, chunk)?) {
+                let Some(value) = value else {
+                    continue;
+                };
+                match record::decode(value) {
+                    Ok(booking) if booking.expires_at.is_some_and(|at| at <= now) => {
+                        expired.push((*id, booking));
+                        if expired.len() == limit {
+                            return Ok(expired);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => logging::log(
+                        logging::Level::Warn,
+                        LOG_CONTEXT,
+                        &format!("Skipping undecodable booking {}: {:?}", id, e),
+                    ),
+                }
+            }
+        }
+        Ok(expired)
     }
 }
 
 impl Guest for Component {
-    fn add_booking(id: u32, subject: _rt::String) -> Result<(), _rt::String> {
+    fn add_booking(tenant: String, id: u32, booking: Booking) -> Result<Booking, BookingError> {
+        validation::booking(&booking)?;
+        let storage = Self::storage(&tenant)?;
+        if Self::exists(&storage, id)? {
+            return Err(BookingError::AlreadyExists);
+        }
+        let booking = Self::insert(&storage, id, booking)?;
+        logging::log(
+            logging::Level::Info,
+            LOG_CONTEXT,
+            &format!("Created booking {}: {}", id, booking.subject),
+        );
+        Ok(booking)
+    }
+
+    fn create_booking(tenant: String, booking: Booking) -> Result<BookingEntry, BookingError> {
+        validation::booking(&booking)?;
+        let storage = Self::storage(&tenant)?;
+        let id = Self::allocate_id(&storage)?;
+        let booking = Self::insert(&storage, id, booking)?;
         logging::log(
             logging::Level::Info,
             LOG_CONTEXT,
-            &format!("Created booking {}: {}", id, subject),
+            &format!("Created booking {}: {}", id, booking.subject),
         );
-        Self::bucket()?
-            .set(&id.to_string(), subject.as_bytes())
-            .map_err(|e| format!("failed to store booking: {e:?}"))
+        Ok(BookingEntry { id, booking })
     }
 
-    fn get_booking(id: u32) -> Result<_rt::String, _rt::String> {
+    fn get_booking(tenant: String, id: u32) -> Result<Booking, BookingError> {
         logging::log(
             logging::Level::Info,
             LOG_CONTEXT,
             &format!("Returning booking {}", id),
         );
-        let val = Self::bucket()?
-            .get(&id.to_string())
-            .map_err(|e| format!("failed to store booking: {e:?}"))?;
-        if let Some(slice) = val {
-            String::from_utf8(slice)
-                .map_err(|e| format!("failed to convert booking to string: {e:?}"))
-        } else {
-            Err(format!("no booking found for id: {id}"))
+        Self::read(&Self::storage(&tenant)?, id)?.ok_or(BookingError::NotFound)
+    }
+
+    fn update_booking(
+        tenant: String,
+        id: u32,
+        booking: Booking,
+        expected_version: u64,
+    ) -> Result<Booking, BookingError> {
+        logging::log(
+            logging::Level::Info,
+            LOG_CONTEXT,
+            &format!("Updating booking {} at version {}", id, expected_version),
+        );
+        validation::booking(&booking)?;
+        let storage = Self::storage(&tenant)?;
+        let current = Self::read(&storage, id)?.ok_or(BookingError::NotFound)?;
+        // Every update based on a stale read is rejected, but a write landing between this check
+        // and the batch write below can still be lost (see `Storage`).
+        if current.version != expected_version {
+            return Err(BookingError::Conflict);
         }
+        let booking = Booking {
+            created_at: current.created_at,
+            updated_at: wall_clock::now().seconds,
+            version: current.version + 1,
+            ..booking
+        };
+        Self::write(&storage, EventKind::Updated, id, &booking, Some(&current))?;
+        Ok(booking)
     }
 
-    fn delete_booking(id: u32) -> Result<(), _rt::String> {
+    fn delete_booking(tenant: String, id: u32) -> Result<Booking, BookingError> {
         logging::log(
             logging::Level::Info,
             LOG_CONTEXT,
             &format!("Deleting booking {}", id),
         );
-        Self::bucket()?
-            .delete(&id.to_string())
-            .map_err(|e| format!("failed to delete booking: {e:?}"))
+        let storage = Self::storage(&tenant)?;
+        let booking = Self::read(&storage, id)?.ok_or(BookingError::NotFound)?;
+        let removed = [(id, booking)];
+        Self::remove(&storage, EventKind::Deleted, &removed)?;
+        let [(_, booking)] = removed;
+        Ok(booking)
+    }
+
+    fn add_bookings(
+        tenant: String,
+        bookings: Vec<(u32, Booking)>,
+    ) -> Result<Vec<Result<Booking, BookingError>>, BookingError> {
+        logging::log(
+            logging::Level::Info,
+            LOG_CONTEXT,
+            &format!("Adding {} bookings", bookings.len()),
+        );
+        validation::batch("bookings", bookings.len())?;
+        let storage = Self::storage(&tenant)?;
+        let ids: Vec<u32> = bookings.iter().map(|(id, _)| *id).collect();
+        let mut taken: HashSet<u32> = ids
+            .iter()
+            .zip(Self::read_many(&storage, &ids)?)
+            .filter_map(|(id, value)| value.map(|_| *id))
+            .collect();
+        let now = wall_clock::now().seconds;
+        let mut index = Indexes::new(&storage);
+        let mut entries = Vec::new();
+        let mut add = |id: u32, booking: Booking| {
+            validation::booking(&booking)?;
+            if taken.contains(&id) {
+                return Err(BookingError::AlreadyExists);
+            }
+            let booking = Booking {
+                created_at: now,
+                updated_at: now,
+                version: 1,
+                ..booking
+            };
+            let mut staged = Vec::new();
+            let event = Self::record(&storage, EventKind::Created, id, &booking, &mut staged)?;
+            index.update(id, None, Some(&booking))?;
+            index.recorded(id, &event);
+            entries.append(&mut staged);
+            taken.insert(id);
+            Ok(booking)
+        };
+        let results: Vec<_> = bookings
+            .into_iter()
+            .map(|(id, booking)| add(id, booking))
+            .collect();
+        entries.extend(index.encode()?);
+        if !entries.is_empty() {
+            batch::set_many(&storage.bucket, &entries).map_err(storage_error("store bookings"))?;
+        }
+        Ok(results)
+    }
+
+    fn get_bookings(
+        tenant: String,
+        ids: Vec<u32>,
+    ) -> Result<Vec<Result<Booking, BookingError>>, BookingError> {
+        logging::log(
+            logging::Level::Info,
+            LOG_CONTEXT,
+            &format!("Returning {} bookings", ids.len()),
+        );
+        validation::batch("ids", ids.len())?;
+        let storage = Self::storage(&tenant)?;
+        Ok(Self::read_many(&storage, &ids)?
+            .into_iter()
+            .map(|value| value.ok_or(BookingError::NotFound).and_then(record::decode))
+            .collect())
+    }
+
+    fn delete_bookings(
+        tenant: String,
+        ids: Vec<u32>,
+    ) -> Result<Vec<Result<Booking, BookingError>>, BookingError> {
+        logging::log(
+            logging::Level::Info,
+            LOG_CONTEXT,
+            &format!("Deleting {} bookings", ids.len()),
+        );
+        validation::batch("ids", ids.len())?;
+        let storage = Self::storage(&tenant)?;
+        let mut seen = HashSet::new();
+        let mut removed = Vec::new();
+        let mut results = Vec::with_capacity(ids.len());
+        for (id, value) in ids.iter().zip(Self::read_many(&storage, &ids)?) {
+            // a booking listed twice is gone by the time the second item is reached
+            let result = value
+                .filter(|_| seen.insert(*id))
+                .ok_or(BookingError::NotFound)
+                .and_then(record::decode);
+            if let Ok(booking) = &result {
+                removed.push((*id, booking.clone()));
+            }
+            results.push(result);
+        }
+        if !removed.is_empty() {
+            Self::remove(&storage, EventKind::Deleted, &removed)?;
+        }
+        Ok(results)
+    }
+
+    fn get_booking_history(tenant: String, id: u32) -> Result<Vec<HistoryEntry>, BookingError> {
+        logging::log(
+            logging::Level::Info,
+            LOG_CONTEXT,
+            &format!("Returning history of booking {}", id),
+        );
+        let storage = Self::storage(&tenant)?;
+        let history = history::read(&storage, id)?;
+        // bookings stored before the history was introduced exist without one
+        if history.is_empty() && !Self::exists(&storage, id)? {
+            return Err(BookingError::NotFound);
+        }
+        Ok(history)
+    }
+
+    fn restore_booking(tenant: String, id: u32) -> Result<Booking, BookingError> {
+        logging::log(
+            logging::Level::Info,
+            LOG_CONTEXT,
+            &format!("Restoring booking {}", id),
+        );
+        let storage = Self::storage(&tenant)?;
+        if Self::exists(&storage, id)? {
+            return Err(BookingError::AlreadyExists);
+        }
+        let last = history::read(&storage, id)?
+            .pop()
+            .ok_or(BookingError::NotFound)?;
+        // versions keep increasing across the deletion, so stale `If-Match` headers still fail
+        let booking = Booking {
+            updated_at: wall_clock::now().seconds,
+            version: last.booking.version + 1,
+            ..last.booking
+        };
+        Self::write(&storage, EventKind::Restored, id, &booking, None)?;
+        Ok(booking)
+    }
+
+    fn list_bookings(
+        tenant: String,
+        cursor: Option<u64>,
+        limit: u32,
+    ) -> Result<BookingPage, BookingError> {
+        logging::log(
+            logging::Level::Info,
+            LOG_CONTEXT,
+            &format!("Listing bookings after {:?}", cursor),
+        );
+        let limit = match limit {
+            0 => DEFAULT_PAGE_SIZE,
+            l => l.min(MAX_PAGE_SIZE),
+        } as usize;
+        let storage = Self::storage(&tenant)?;
+        let mut ids: Vec<u32> = if listing::backfilled(&storage)? {
+            listing::read(&storage)?.into_iter().collect()
+        } else {
+            Self::booking_ids(&storage)?
+        };
+        ids.retain(|id| cursor.is_none_or(|c| u64::from(*id) > c));
+        let next_cursor = (ids.len() > limit).then(|| u64::from(ids[limit - 1]));
+        ids.truncate(limit);
+
+        let mut bookings = Vec::with_capacity(ids.len());
+        for id in ids {
+            // the booking might have been deleted since it was listed
+            if let Some(booking) = Self::read(&storage, id)? {
+                bookings.push(BookingEntry { id, booking });
+            }
+        }
+        Ok(BookingPage {
+            bookings,
+            next_cursor,
+        })
+    }
+
+    fn search_bookings(
+        tenant: String,
+        query: String,
+        limit: u32,
+    ) -> Result<Vec<BookingEntry>, BookingError> {
+        logging::log(
+            logging::Level::Info,
+            LOG_CONTEXT,
+            &format!("Searching bookings for {:?}", query),
+        );
+        validation::query(&query)?;
+        let limit = match limit {
+            0 => DEFAULT_PAGE_SIZE,
+            l => l.min(MAX_PAGE_SIZE),
+        } as usize;
+        let storage = Self::storage(&tenant)?;
+        let mut matches: Option<BTreeSet<u32>> = None;
+        for token in search::tokens(&query) {
+            let ids = search::read(&storage, &token)?.into_iter();
+            let ids = match matches {
+                None => ids.collect(),
+                Some(previous) => ids.filter(|id| previous.contains(id)).collect(),
+            };
+            matches = Some(ids);
+        }
+        let ids: Vec<u32> = matches.into_iter().flatten().take(limit).collect();
+        let mut bookings = Vec::with_capacity(ids.len());
+        // the booking might have been deleted since the index was read
+        for (id, value) in ids.iter().zip(Self::read_many(&storage, &ids)?) {
+            if let Some(value) = value {
+                bookings.push(BookingEntry {
+                    id: *id,
+                    booking: record::decode(value)?,
+                });
+            }
+        }
+        Ok(bookings)
+    }
+
+    fn check_availability(
+        tenant: String,
+        resource: String,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<u32>, BookingError> {
+        logging::log(
+            logging::Level::Info,
+            LOG_CONTEXT,
+            &format!(
+                "Checking availability of {} from {} to {}",
+                resource, start, end
+            ),
+        );
+        validation::availability(&resource, start, end)?;
+        let reservations = reservation::read(&Self::storage(&tenant)?, &resource)?;
+        Ok(reservation::conflicts(&reservations, start, end))
+    }
+
+    fn sweep_expired(limit: u32) -> Result<u32, BookingError> {
+        let limit = match limit {
+            0 => DEFAULT_SWEEP_BATCH,
+            l => l.min(MAX_PAGE_SIZE),
+        } as usize;
+        let now = wall_clock::now().seconds;
+        // the keys of all tenants are listed through the default tenant's storage
+        let storage = Self::storage("")?;
+        let keys = Self::all_keys(&storage)?;
+        let mut swept = 0;
+        for (tenant, ids) in Self::tenants(&storage, &keys) {
+            if swept == limit {
+                break;
+            }
+            let storage = Self::storage(&tenant)?;
+            let expired = Self::expired(&storage, &ids, now, limit - swept)?;
+            if expired.is_empty() {
+                continue;
+            }
+            logging::log(
+                logging::Level::Info,
+                LOG_CONTEXT,
+                &format!(
+                    "Archiving {} expired bookings of tenant {:?}",
+                    expired.len(),
+                    tenant
+                ),
+            );
+            Self::remove(&storage, EventKind::Expired, &expired)?;
+            swept += expired.len();
+        }
+        Ok(swept as u32)
+    }
+
+    fn reconcile_indexes() -> Result<u32, BookingError> {
+        // the keys of all tenants are listed through the default tenant's storage
+        let storage = Self::storage("")?;
+        let keys = Self::all_keys(&storage)?;
+        let mut added = outbox::reconcile(&storage, &keys)?;
+        if added > 0 {
+            logging::log(
+                logging::Level::Warn,
+                LOG_CONTEXT,
+                &format!("Added {} events missing from the outbox index", added),
+            );
+        }
+        for (tenant, ids) in Self::tenants(&storage, &keys) {
+            let storage = Self::storage(&tenant)?;
+            let unlisted = history::reconcile(&storage, &keys)?;
+            if unlisted > 0 {
+                logging::log(
+                    logging::Level::Warn,
+                    LOG_CONTEXT,
+                    &format!(
+                        "Added {} history entries of tenant {:?} missing from their index",
+                        unlisted, tenant
+                    ),
+                );
+            }
+            added += unlisted;
+            let unlisted = listing::reconcile(&storage, &ids)?;
+            if unlisted > 0 {
+                logging::log(
+                    logging::Level::Warn,
+                    LOG_CONTEXT,
+                    &format!(
+                        "Added {} bookings of tenant {:?} missing from the booking index",
+                        unlisted, tenant
+                    ),
+                );
+            }
+            added += unlisted;
+            if !search::backfilled(&storage)? {
+                logging::log(
+                    logging::Level::Info,
+                    LOG_CONTEXT,
+                    &format!(
+                        "Indexing {} bookings of tenant {:?} for search",
+                        ids.len(),
+                        tenant
+                    ),
+                );
+                Self::backfill_search(&storage, &ids)?;
+                added += ids.len();
+            }
+        }
+        Ok(u32::try_from(added).unwrap_or(u32::MAX))
+    }
+
+    fn pending_events(limit: u32) -> Result<Vec<OutboxEvent>, BookingError> {
+        let limit = match limit {
+            0 => DEFAULT_EVENT_BATCH,
+            l => l.min(MAX_PAGE_SIZE),
+        } as usize;
+        // the outbox is shared by all tenants, the default tenant's storage reaches it as well
+        outbox::pending(&Self::storage("")?, limit)
+    }
+
+    fn ack_event(key: String) -> Result<(), BookingError> {
+        logging::log(
+            logging::Level::Debug,
+            LOG_CONTEXT,
+            &format!("Acknowledging event {}", key),
+        );
+        let storage = Self::storage("")?;
+        storage
+            .bucket
+            .delete(&storage.keys.outbox(&key))
+            .map_err(storage_error("acknowledge event"))
     }
 }
 
@@ -0,0 +1,130 @@
+//! Index of the IDs of the bookings of a tenant.
+//!
+//! The IDs are stored together under a key of the tenant, and updated in the same batch write as
+//! the bookings they list, so that listing the bookings of a tenant does not list every key of
+//! the bucket. Bookings stored before the index existed are listed by the first run of
+//! `reconcile-indexes`, which then marks the tenant as listed; until then, bookings are listed from
+//! the keys of the bucket. IDs lost to concurrent changes (see [`Storage`]) are added again by every
+//! run.
+
+use std::collections::BTreeSet;
+
+use crate::exports::demo::booking_master::booking_management::{Booking, BookingError};
+use crate::{storage_error, Storage};
+
+/// Whether the bookings stored before the index existed have been listed.
+pub fn backfilled(storage: &Storage) -> Result<bool, BookingError> {
+    storage
+        .bucket
+        .exists(&storage.keys.booking_index_backfilled())
+        .map_err(storage_error("read booking index"))
+}
+
+/// Read the IDs of the bookings of the tenant, in ascending order.
+pub fn read(storage: &Storage) -> Result<BTreeSet<u32>, BookingError> {
+    let Some(bytes) = storage
+        .bucket
+        .get(&storage.keys.booking_index())
+        .map_err(storage_error("read booking index"))?
+    else {
+        return Ok(BTreeSet::new());
+    };
+    serde_json::from_slice(&bytes)
+        .map_err(|e| BookingError::Storage(format!("failed to decode booking index: {e}")))
+}
+
+fn encode(ids: &BTreeSet<u32>) -> Result<Vec<u8>, BookingError> {
+    serde_json::to_vec(ids)
+        .map_err(|e| BookingError::Storage(format!("failed to encode booking index: {e}")))
+}
+
+/// Add the IDs among `ids` which are missing from the index, and mark the bookings stored before
+/// the index existed as listed. Returns the number of added IDs.
+///
+/// IDs of deleted bookings are left in place, as the bookings might have been stored since `ids`
+/// were collected. Listing skips them.
+pub fn reconcile(storage: &Storage, ids: &[u32]) -> Result<usize, BookingError> {
+    let mut listed = read(storage)?;
+    let before = listed.len();
+    listed.extend(ids);
+    let missing = listed.len() - before;
+    if missing > 0 {
+        storage
+            .bucket
+            .set(&storage.keys.booking_index(), &encode(&listed)?)
+            .map_err(storage_error("store booking index"))?;
+    }
+    storage
+        .bucket
+        .set(&storage.keys.booking_index_backfilled(), b"1")
+        .map_err(storage_error("store booking index"))?;
+    Ok(missing)
+}
+
+/// Whether a change adds or removes a booking, see [`Index::prepare`].
+pub enum Change {
+    Added,
+    Removed,
+    Unchanged,
+}
+
+/// The IDs of the bookings of a tenant, updated in memory so that a set of changes can be stored
+/// in a single batch write.
+pub struct Index<'a> {
+    storage: &'a Storage,
+    /// The IDs, once read.
+    ids: Option<BTreeSet<u32>>,
+    changed: bool,
+}
+
+impl<'a> Index<'a> {
+    pub fn new(storage: &'a Storage) -> Self {
+        Index {
+            storage,
+            ids: None,
+            changed: false,
+        }
+    }
+
+    /// Read the index if a booking changing from `previous` to `next` is added or removed.
+    ///
+    /// Reading is the only step which can fail, so it is separate from [`Index::apply`].
+    pub fn prepare(
+        &mut self,
+        previous: Option<&Booking>,
+        next: Option<&Booking>,
+    ) -> Result<Change, BookingError> {
+        let change = match (previous, next) {
+            (None, Some(_)) => Change::Added,
+            (Some(_), None) => Change::Removed,
+            _ => return Ok(Change::Unchanged),
+        };
+        if self.ids.is_none() {
+            self.ids = Some(read(self.storage)?);
+        }
+        Ok(change)
+    }
+
+    /// Record a change of the booking with ID `id` read by [`Index::prepare`].
+    pub fn apply(&mut self, id: u32, change: Change) {
+        let Some(ids) = &mut self.ids else {
+            return;
+        };
+        match change {
+            Change::Added => ids.insert(id),
+            Change::Removed => ids.remove(&id),
+            Change::Unchanged => return,
+        };
+        self.changed = true;
+    }
+
+    /// Serialize the index into the key/value pairs to store, if it changed.
+    pub fn encode(self) -> Result<Vec<(String, Vec<u8>)>, BookingError> {
+        match self.ids {
+            Some(ids) if self.changed => {
+                Ok(vec![(self.storage.keys.booking_index(), encode(&ids)?)])
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+}
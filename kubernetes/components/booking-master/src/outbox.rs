@@ -0,0 +1,282 @@
+//! Transactional outbox of booking events.
+//!
+//! Every change to a booking is accompanied by an event, written under the outbox prefix of the
+//! bucket in the same batch as the booking itself. A relay drains the outbox to the messaging bus
+//! and acknowledges published events, which removes them from the bucket. Events are thus delivered
+//! at least once, and their key doubles as idempotency key for consumers.
+//!
+//! The keys of the pending events are listed in an index of their own, so that the relay does not
+//! have to list every key of the bucket. Every event is listed in a slot of its own, written in the
+//! same batch as the event. Slots are numbered by an atomic counter, so concurrent writes never
+//! overwrite each other's slots, and the head of the index records the slot up to which all events
+//! are gone. Acknowledging an event only deletes the event; its slot is deleted the next time
+//! pending events are read. A slot written only after the head moved past it is not read again;
+//! `reconcile-indexes` lists every key anyway and lists such events, and events recorded before the
+//! slots existed, in new slots.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::exports::demo::booking_master::booking_management::{
+    Booking, BookingError, EventKind, OutboxEvent,
+};
+use crate::record::StoredBooking;
+use crate::wasi::clocks::wall_clock;
+use crate::wasi::keyvalue::{atomics, batch};
+use crate::{storage_error, Storage, CHUNK_SIZE};
+
+#[derive(Serialize, Deserialize)]
+struct StoredEvent {
+    #[serde(default)]
+    tenant: String,
+    kind: StoredKind,
+    booking_id: u32,
+    booking: StoredBooking,
+    occurred_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum StoredKind {
+    Created,
+    Updated,
+    Deleted,
+    Restored,
+    Expired,
+}
+
+/// Record a change of the booking of `tenant` with ID `id`.
+///
+/// The key starts with the time of the change, so that sorting keys orders events by their
+/// occurrence. The booking ID and version make it unique.
+pub fn event(kind: EventKind, tenant: &str, id: u32, booking: &Booking) -> OutboxEvent {
+    let now = wall_clock::now();
+    let nanos = now.seconds * 1_000_000_000 + u64::from(now.nanoseconds);
+    OutboxEvent {
+        key: format!("{nanos:020}-{id:010}-{}", booking.version),
+        tenant: tenant.to_string(),
+        kind,
+        booking_id: id,
+        booking: booking.clone(),
+        occurred_at: now.seconds,
+    }
+}
+
+/// Serialize an event into the bytes stored in the bucket.
+pub fn encode(event: &OutboxEvent) -> Result<Vec<u8>, BookingError> {
+    let stored = StoredEvent {
+        tenant: event.tenant.clone(),
+        kind: match event.kind {
+            EventKind::Created => StoredKind::Created,
+            EventKind::Updated => StoredKind::Updated,
+            EventKind::Deleted => StoredKind::Deleted,
+            EventKind::Restored => StoredKind::Restored,
+            EventKind::Expired => StoredKind::Expired,
+        },
+        booking_id: event.booking_id,
+        booking: (&event.booking).into(),
+        occurred_at: event.occurred_at,
+    };
+    serde_json::to_vec(&stored)
+        .map_err(|e| BookingError::Storage(format!("failed to encode event: {e}")))
+}
+
+/// Deserialize the event stored under `key`.
+pub fn decode(key: String, bytes: &[u8]) -> Result<OutboxEvent, BookingError> {
+    let stored: StoredEvent = serde_json::from_slice(bytes)
+        .map_err(|e| BookingError::Storage(format!("failed to decode event {key}: {e}")))?;
+    Ok(OutboxEvent {
+        key,
+        tenant: stored.tenant,
+        kind: match stored.kind {
+            StoredKind::Created => EventKind::Created,
+            StoredKind::Updated => EventKind::Updated,
+            StoredKind::Deleted => EventKind::Deleted,
+            StoredKind::Restored => EventKind::Restored,
+            StoredKind::Expired => EventKind::Expired,
+        },
+        booking_id: stored.booking_id,
+        booking: stored.booking.into(),
+        occurred_at: stored.occurred_at,
+    })
+}
+
+/// Read the number of the last slot of the outbox index known to be done with.
+fn read_head(storage: &Storage) -> Result<u64, BookingError> {
+    let Some(bytes) = storage
+        .bucket
+        .get(&storage.keys.outbox_head())
+        .map_err(storage_error("read outbox index"))?
+    else {
+        return Ok(0);
+    };
+    std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|head| head.parse().ok())
+        .ok_or_else(|| BookingError::Storage("failed to decode outbox index head".to_string()))
+}
+
+/// Read the event keys listed in the given slots, keyed by slot. Empty slots are missing.
+fn read_slots(
+    storage: &Storage,
+    slots: impl Iterator<Item = u64>,
+) -> Result<BTreeMap<u64, String>, BookingError> {
+    let slots: Vec<u64> = slots.collect();
+    let mut listed = BTreeMap::new();
+    for chunk in slots.chunks(CHUNK_SIZE) {
+        let keys: Vec<String> = chunk
+            .iter()
+            .map(|slot| storage.keys.outbox_slot(*slot))
+            .collect();
+        let found: HashMap<String, Vec<u8>> = batch::get_many(&storage.bucket, &keys)
+            .map_err(storage_error("read outbox index"))?
+            .into_iter()
+            .flatten()
+            .collect();
+        for (slot, key) in chunk.iter().zip(&keys) {
+            if let Some(value) = found.get(key) {
+                let event = String::from_utf8(value.clone()).map_err(|e| {
+                    BookingError::Storage(format!("failed to decode outbox slot {slot}: {e}"))
+                })?;
+                listed.insert(*slot, event);
+            }
+        }
+    }
+    Ok(listed)
+}
+
+/// Events recorded by a set of changes, listed in the outbox index in the same batch write as the
+/// events themselves.
+pub struct Index<'a> {
+    storage: &'a Storage,
+    added: BTreeSet<String>,
+}
+
+impl<'a> Index<'a> {
+    pub fn new(storage: &'a Storage) -> Self {
+        Index {
+            storage,
+            added: BTreeSet::new(),
+        }
+    }
+
+    /// List the event with the given key.
+    pub fn add(&mut self, event: &str) {
+        self.added.insert(event.to_string());
+    }
+
+    /// Allocate a slot for every added event, and serialize the slots into the key/value pairs to
+    /// store.
+    ///
+    /// Slots allocated for a batch write which then fails remain empty.
+    pub fn encode(self) -> Result<Vec<(String, Vec<u8>)>, BookingError> {
+        if self.added.is_empty() {
+            return Ok(Vec::new());
+        }
+        let count = self.added.len() as u64;
+        let last = atomics::increment(
+            &self.storage.bucket,
+            &self.storage.keys.outbox_sequence(),
+            count,
+        )
+        .map_err(storage_error("allocate outbox slots"))?;
+        Ok((last + 1 - count..)
+            .zip(self.added)
+            .map(|(slot, event)| (self.storage.keys.outbox_slot(slot), event.into_bytes()))
+            .collect())
+    }
+}
+
+/// Read the oldest events listed in the outbox index, at most `limit`.
+///
+/// Slots of events which no longer exist, because they were acknowledged or withdrawn, are
+/// deleted, and the head of the index moves past the slots preceding the oldest pending event.
+pub fn pending(storage: &Storage, limit: usize) -> Result<Vec<OutboxEvent>, BookingError> {
+    let head = read_head(storage)?;
+    // adding nothing reads the counter
+    let last = atomics::increment(&storage.bucket, &storage.keys.outbox_sequence(), 0)
+        .map_err(storage_error("read outbox index"))?;
+    let mut events = Vec::new();
+    let mut gone = Vec::new();
+    let mut done = head;
+    let mut start = head + 1;
+    while start <= last && events.len() < limit {
+        let end = last.min(start + limit as u64 - 1);
+        let listed = read_slots(storage, start..=end)?;
+        let keys: Vec<String> = listed
+            .values()
+            .map(|event| storage.keys.outbox(event))
+            .collect();
+        let mut found: HashMap<String, Vec<u8>> = HashMap::new();
+        for chunk in keys.chunks(CHUNK_SIZE) {
+            found.extend(
+                batch::get_many(&storage.bucket, chunk)
+                    .map_err(storage_error("read events"))?
+                    .into_iter()
+                    .flatten(),
+            );
+        }
+        for slot in start..=end {
+            let value = listed
+                .get(&slot)
+                .map(|event| (event, found.remove(&storage.keys.outbox(event))));
+            match value {
+                Some((event, Some(value))) if events.len() < limit => {
+                    events.push(decode(event.clone(), &value)?);
+                }
+                // a second slot listing the same event finds it taken, and is deleted as well
+                Some((_, None)) => gone.push(storage.keys.outbox_slot(slot)),
+                _ => {}
+            }
+            if events.is_empty() {
+                done = slot;
+            }
+        }
+        start = end + 1;
+    }
+    for chunk in gone.chunks(CHUNK_SIZE) {
+        batch::delete_many(&storage.bucket, chunk).map_err(storage_error("store outbox index"))?;
+    }
+    if done > head {
+        storage
+            .bucket
+            .set(&storage.keys.outbox_head(), done.to_string().as_bytes())
+            .map_err(storage_error("store outbox index"))?;
+    }
+    Ok(events)
+}
+
+/// List the events among `keys` which are missing from the outbox index, returning their number.
+///
+/// Slots at or before the head of the index are deleted, and the events they list count as
+/// missing: they were written after the head moved past them.
+pub fn reconcile(storage: &Storage, keys: &[String]) -> Result<usize, BookingError> {
+    let head = read_head(storage)?;
+    let (stale, slots): (Vec<u64>, Vec<u64>) = keys
+        .iter()
+        .filter_map(|key| storage.keys.parse_outbox_slot(key))
+        .partition(|slot| *slot <= head);
+    let listed: BTreeSet<String> = read_slots(storage, slots.into_iter())?
+        .into_values()
+        .collect();
+    let mut index = Index::new(storage);
+    for event in keys.iter().filter_map(|key| storage.keys.parse_outbox(key)) {
+        if !listed.contains(event) {
+            index.add(event);
+        }
+    }
+    let missing = index.added.len();
+    let entries = index.encode()?;
+    for chunk in entries.chunks(CHUNK_SIZE) {
+        batch::set_many(&storage.bucket, chunk).map_err(storage_error("store outbox index"))?;
+    }
+    let stale: Vec<String> = stale
+        .into_iter()
+        .map(|slot| storage.keys.outbox_slot(slot))
+        .collect();
+    for chunk in stale.chunks(CHUNK_SIZE) {
+        batch::delete_many(&storage.bucket, chunk).map_err(storage_error("store outbox index"))?;
+    }
+    Ok(missing)
+}
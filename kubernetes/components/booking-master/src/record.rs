@@ -0,0 +1,199 @@
+//! Encoding of bookings as they are stored in the key/value bucket.
+//!
+//! Bookings are written as a JSON envelope carrying an encoding version, so that the layout can
+//! evolve without breaking records written by older deployments. Values which are not an envelope
+//! are bookings stored before structured records existed: these are plain UTF-8 subjects.
+
+use serde::{Deserialize, Serialize};
+
+use crate::exports::demo::booking_master::booking_management::{
+    Booking, BookingError, BookingStatus,
+};
+
+/// Encoding version written by this component.
+const ENCODING_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    v: u32,
+    booking: StoredBooking,
+}
+
+/// The version of an envelope, read on its own when the rest of the envelope does not match.
+#[derive(Deserialize)]
+struct Version {
+    v: u32,
+}
+
+/// Serialized form of a [`Booking`].
+#[derive(Serialize, Deserialize)]
+pub struct StoredBooking {
+    subject: String,
+    guest_name: String,
+    resource: String,
+    start: u64,
+    end: u64,
+    status: StoredStatus,
+    created_at: u64,
+    #[serde(default)]
+    updated_at: u64,
+    version: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum StoredStatus {
+    Pending,
+    Confirmed,
+    Cancelled,
+}
+
+/// Serialize a booking into the bytes stored in the bucket.
+pub fn encode(booking: &Booking) -> Result<Vec<u8>, BookingError> {
+    let envelope = Envelope {
+        v: ENCODING_VERSION,
+        booking: booking.into(),
+    };
+    serde_json::to_vec(&envelope)
+        .map_err(|e| BookingError::Storage(format!("failed to encode booking: {e}")))
+}
+
+/// Deserialize a booking read from the bucket, accepting legacy plain string records.
+pub fn decode(bytes: Vec<u8>) -> Result<Booking, BookingError> {
+    match serde_json::from_slice::<Envelope>(&bytes) {
+        Ok(Envelope { v, booking }) if v == ENCODING_VERSION => Ok(booking.into()),
+        Ok(Envelope { v, .. }) => Err(BookingError::Storage(format!(
+            "unsupported booking encoding version: {v}"
+        ))),
+        Err(e) => match serde_json::from_slice::<Version>(&bytes) {
+            // envelopes of other versions may lay out the booking differently
+            Ok(Version { v }) if v != ENCODING_VERSION => Err(BookingError::Storage(format!(
+                "unsupported booking encoding version: {v}"
+            ))),
+            Ok(_) => Err(BookingError::Storage(format!(
+                "failed to decode booking: {e}"
+            ))),
+            Err(_) => legacy(bytes),
+        },
+    }
+}
+
+fn legacy(bytes: Vec<u8>) -> Result<Booking, BookingError> {
+    let subject = String::from_utf8(bytes).map_err(|e| {
+        BookingError::Storage(format!("failed to convert booking to string: {e:?}"))
+    })?;
+    Ok(Booking {
+        subject,
+        guest_name: String::new(),
+        resource: String::new(),
+        start: 0,
+        end: 0,
+        status: BookingStatus::Confirmed,
+        created_at: 0,
+        updated_at: 0,
+        version: 0,
+        expires_at: None,
+    })
+}
+
+impl From<&Booking> for StoredBooking {
+    fn from(booking: &Booking) -> Self {
+        StoredBooking {
+            subject: booking.subject.clone(),
+            guest_name: booking.guest_name.clone(),
+            resource: booking.resource.clone(),
+            start: booking.start,
+            end: booking.end,
+            status: booking.status.into(),
+            created_at: booking.created_at,
+            updated_at: booking.updated_at,
+            version: booking.version,
+            expires_at: booking.expires_at,
+        }
+    }
+}
+
+impl From<StoredBooking> for Booking {
+    fn from(booking: StoredBooking) -> Self {
+        Booking {
+            subject: booking.subject,
+            guest_name: booking.guest_name,
+            resource: booking.resource,
+            start: booking.start,
+            end: booking.end,
+            status: booking.status.into(),
+            created_at: booking.created_at,
+            updated_at: booking.updated_at,
+            version: booking.version,
+            expires_at: booking.expires_at,
+        }
+    }
+}
+
+impl From<BookingStatus> for StoredStatus {
+    fn from(status: BookingStatus) -> Self {
+        match status {
+            BookingStatus::Pending => StoredStatus::Pending,
+            BookingStatus::Confirmed => StoredStatus::Confirmed,
+            BookingStatus::Cancelled => StoredStatus::Cancelled,
+        }
+    }
+}
+
+impl From<StoredStatus> for BookingStatus {
+    fn from(status: StoredStatus) -> Self {
+        match status {
+            StoredStatus::Pending => BookingStatus::Pending,
+            StoredStatus::Confirmed => BookingStatus::Confirmed,
+            StoredStatus::Cancelled => BookingStatus::Cancelled,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn bookings_round_trip() {
+        let booking = Booking {
+            resource: "room-1".to_string(),
+            start: 10,
+            end: 20,
+            version: 3,
+            ..testing::booking("Room")
+        };
+        let decoded = decode(encode(&booking).unwrap()).unwrap();
+        assert_eq!(format!("{decoded:?}"), format!("{booking:?}"));
+    }
+
+    #[test]
+    fn plain_strings_are_legacy_bookings() {
+        let booking = decode(b"Meeting with Alice".to_vec()).unwrap();
+        assert_eq!(booking.subject, "Meeting with Alice");
+        assert_eq!(booking.guest_name, "");
+        assert_eq!(booking.resource, "");
+        assert_eq!((booking.start, booking.end), (0, 0));
+        assert_eq!(booking.status, BookingStatus::Confirmed);
+        assert_eq!((booking.created_at, booking.version), (0, 0));
+        assert!(decode(vec![0xff, 0xfe]).is_err());
+    }
+
+    #[test]
+    fn unknown_envelope_versions_are_rejected() {
+        let unsupported = |bytes: &[u8]| {
+            matches!(
+                decode(bytes.to_vec()),
+                Err(BookingError::Storage(message)) if message.contains("version: 2")
+            )
+        };
+        let current = String::from_utf8(encode(&testing::booking("Room")).unwrap()).unwrap();
+        assert!(unsupported(
+            current.replace(r#""v":1"#, r#""v":2"#).as_bytes()
+        ));
+        assert!(unsupported(br#"{"v":2,"booking":{"title":"Room"}}"#));
+    }
+}
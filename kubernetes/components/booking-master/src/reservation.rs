@@ -0,0 +1,216 @@
+//! Index of the time ranges reserved on every resource.
+//!
+//! The reservations of a resource are stored together under a key of their own, so that overlaps
+//! and availability can be checked without reading every booking. The index is updated in the same
+//! batch write as the bookings it describes.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::exports::demo::booking_master::booking_management::{
+    Booking, BookingError, BookingStatus,
+};
+use crate::keys::Keys;
+use crate::{storage_error, Storage};
+
+/// Time range from `start` (inclusive) to `end` (exclusive) reserved by the booking with ID `id`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reservation {
+    pub id: u32,
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Reservation {
+    /// The reservation made by a booking, if it reserves its resource.
+    pub fn of(id: u32, booking: &Booking) -> Option<Reservation> {
+        (!booking.resource.is_empty() && booking.status != BookingStatus::Cancelled).then_some(
+            Reservation {
+                id,
+                start: booking.start,
+                end: booking.end,
+            },
+        )
+    }
+
+    fn overlaps(&self, start: u64, end: u64) -> bool {
+        self.start < end && start < self.end
+    }
+}
+
+/// Read the reservations of `resource`, ordered by start.
+pub fn read(storage: &Storage, resource: &str) -> Result<Vec<Reservation>, BookingError> {
+    let Some(bytes) = storage
+        .bucket
+        .get(&storage.keys.resource(resource))
+        .map_err(storage_error("read reservations"))?
+    else {
+        return Ok(Vec::new());
+    };
+    serde_json::from_slice(&bytes).map_err(|e| {
+        BookingError::Storage(format!("failed to decode reservations of {resource}: {e}"))
+    })
+}
+
+/// Serialize the reservations of `resource` into the key/value pair stored in the bucket.
+pub fn encode(
+    keys: &Keys,
+    resource: &str,
+    mut reservations: Vec<Reservation>,
+) -> Result<(String, Vec<u8>), BookingError> {
+    reservations.sort_by_key(|r| (r.start, r.id));
+    let bytes = serde_json::to_vec(&reservations).map_err(|e| {
+        BookingError::Storage(format!("failed to encode reservations of {resource}: {e}"))
+    })?;
+    Ok((keys.resource(resource), bytes))
+}
+
+/// Reservations of the resources touched by a set of changes, updated in memory so that the
+/// changes can be stored in a single batch write.
+pub struct Index<'a> {
+    storage: &'a Storage,
+    resources: BTreeMap<String, Vec<Reservation>>,
+    /// Resources whose reservations were changed.
+    changed: BTreeSet<String>,
+}
+
+impl<'a> Index<'a> {
+    pub fn new(storage: &'a Storage) -> Self {
+        Index {
+            storage,
+            resources: BTreeMap::new(),
+            changed: BTreeSet::new(),
+        }
+    }
+
+    /// Record that the booking with ID `id` changes from `previous` to `next`.
+    ///
+    /// Fails with [`BookingError::Overlap`] if `next` reserves its resource at a time already
+    /// reserved by another booking, leaving the index unchanged.
+    pub fn update(
+        &mut self,
+        id: u32,
+        previous: Option<&Booking>,
+        next: Option<&Booking>,
+    ) -> Result<(), BookingError> {
+        let touched: Vec<&str> = previous
+            .into_iter()
+            .chain(next)
+            .filter_map(|b| Reservation::of(id, b).map(|_| b.resource.as_str()))
+            .collect();
+        for resource in &touched {
+            if !self.resources.contains_key(*resource) {
+                let reservations = read(self.storage, resource)?;
+                self.resources.insert(resource.to_string(), reservations);
+            }
+        }
+        let reserved = next.and_then(|b| Reservation::of(id, b).map(|r| (b.resource.as_str(), r)));
+        if let Some((resource, reserved)) = &reserved {
+            if let Some(other) = conflicts(&self.resources[*resource], reserved.start, reserved.end)
+                .into_iter()
+                .find(|other| *other != id)
+            {
+                return Err(BookingError::Overlap(other));
+            }
+        }
+        for resource in touched {
+            if let Some(reservations) = self.resources.get_mut(resource) {
+                reservations.retain(|r| r.id != id);
+            }
+            self.changed.insert(resource.to_string());
+        }
+        if let Some((resource, reserved)) = reserved {
+            if let Some(reservations) = self.resources.get_mut(resource) {
+                reservations.push(reserved);
+            }
+        }
+        Ok(())
+    }
+
+    /// Serialize the reservations of every changed resource into the key/value pairs to store.
+    pub fn encode(mut self) -> Result<Vec<(String, Vec<u8>)>, BookingError> {
+        self.changed
+            .iter()
+            .map(|resource| {
+                let reservations = self.resources.remove(resource).unwrap_or_default();
+                encode(&self.storage.keys, resource, reservations)
+            })
+            .collect()
+    }
+}
+
+/// IDs of the bookings whose reservation overlaps `start` to `end`.
+pub fn conflicts(reservations: &[Reservation], start: u64, end: u64) -> Vec<u32> {
+    reservations
+        .iter()
+        .filter(|r| r.overlaps(start, end))
+        .map(|r| r.id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn reserving(status: BookingStatus, start: u64, end: u64) -> Booking {
+        Booking {
+            resource: "room-1".into(),
+            start,
+            end,
+            status,
+            ..testing::booking("Room")
+        }
+    }
+
+    #[test]
+    fn only_active_bookings_of_a_resource_reserve_it() {
+        let active = reserving(BookingStatus::Pending, 10, 20);
+        assert_eq!(
+            Reservation::of(1, &active),
+            Some(Reservation {
+                id: 1,
+                start: 10,
+                end: 20
+            })
+        );
+        let cancelled = reserving(BookingStatus::Cancelled, 10, 20);
+        assert_eq!(Reservation::of(1, &cancelled), None);
+        assert_eq!(Reservation::of(1, &testing::booking("Room")), None);
+    }
+
+    #[test]
+    fn ranges_touching_at_their_ends_do_not_conflict() {
+        let reservations = [Reservation {
+            id: 1,
+            start: 10,
+            end: 20,
+        }];
+        assert!(conflicts(&reservations, 0, 10).is_empty());
+        assert!(conflicts(&reservations, 20, 30).is_empty());
+        assert_eq!(conflicts(&reservations, 15, 25), vec![1]);
+        assert_eq!(conflicts(&reservations, 0, 30), vec![1]);
+    }
+
+    #[test]
+    fn encoded_reservations_are_ordered_by_start() {
+        let reservations = vec![
+            Reservation {
+                id: 2,
+                start: 30,
+                end: 40,
+            },
+            Reservation {
+                id: 1,
+                start: 10,
+                end: 20,
+            },
+        ];
+        let keys = Keys::new("demo:".into(), "");
+        let (key, bytes) = encode(&keys, "room-1", reservations).unwrap();
+        assert_eq!(key, "demo:resource:room-1");
+        let decoded: Vec<Reservation> = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.iter().map(|r| r.id).collect::<Vec<_>>(), vec![1, 2]);
+    }
+}
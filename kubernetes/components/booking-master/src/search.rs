@@ -0,0 +1,205 @@
+//! Index of the words occurring in the subject, guest name and resource of every booking.
+//!
+//! Text is split into tokens at every character which is not a letter or digit, and tokens are
+//! lowercased. The IDs of the bookings containing a token are stored together under a key of their
+//! own, and updated in the same batch write as the bookings they describe. Bookings stored before
+//! the index existed are indexed by the first run of `reconcile-indexes`, which then marks the
+//! tenant as indexed. A booking changed while it is indexed that way can remain listed under tokens it lost.
+//!
+//! A batch can only set or only delete keys, so a token losing its last booking is stored empty
+//! with the change, and its key is deleted by [`prune`] once the change is stored.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::exports::demo::booking_master::booking_management::{Booking, BookingError};
+use crate::wasi::keyvalue::batch;
+use crate::{storage_error, Storage};
+
+/// Maximum length of an indexed token, in characters. Longer tokens are not indexed.
+pub const MAX_TOKEN_LEN: usize = 64;
+
+/// Split `text` into words, before their length is limited.
+pub fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+}
+
+/// Split `text` into the tokens it is indexed and searched by.
+pub fn tokens(text: &str) -> BTreeSet<String> {
+    words(text)
+        .filter(|word| word.chars().count() <= MAX_TOKEN_LEN)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Whether the bookings stored before the index existed have been indexed.
+pub fn backfilled(storage: &Storage) -> Result<bool, BookingError> {
+    storage
+        .bucket
+        .exists(&storage.keys.search_backfilled())
+        .map_err(storage_error("read search index"))
+}
+
+/// Record that the bookings stored before the index existed have been indexed.
+pub fn mark_backfilled(storage: &Storage) -> Result<(), BookingError> {
+    storage
+        .bucket
+        .set(&storage.keys.search_backfilled(), b"1")
+        .map_err(storage_error("store search index"))
+}
+
+/// Tokens of all indexed fields of a booking.
+fn booking_tokens(booking: Option<&Booking>) -> BTreeSet<String> {
+    booking.map_or_else(BTreeSet::new, |b| {
+        [&b.subject, &b.guest_name, &b.resource]
+            .into_iter()
+            .flat_map(|text| tokens(text))
+            .collect()
+    })
+}
+
+/// Read the IDs of the bookings containing `token`, in ascending order.
+pub fn read(storage: &Storage, token: &str) -> Result<Vec<u32>, BookingError> {
+    let Some(bytes) = storage
+        .bucket
+        .get(&storage.keys.search(token))
+        .map_err(storage_error("read search index"))?
+    else {
+        return Ok(Vec::new());
+    };
+    serde_json::from_slice(&bytes).map_err(|e| {
+        BookingError::Storage(format!("failed to decode search index of {token}: {e}"))
+    })
+}
+
+/// Delete the entries under `keys` which are still empty. Entries which gained bookings since
+/// they were stored empty are kept.
+pub fn prune(storage: &Storage, keys: &[String]) -> Result<(), BookingError> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+    let empty: Vec<String> = batch::get_many(&storage.bucket, keys)
+        .map_err(storage_error("read search index"))?
+        .into_iter()
+        .flatten()
+        .filter(|(_, value)| value == b"[]")
+        .map(|(key, _)| key)
+        .collect();
+    if empty.is_empty() {
+        return Ok(());
+    }
+    batch::delete_many(&storage.bucket, &empty).map_err(storage_error("prune search index"))
+}
+
+/// Tokens a booking gains and loses through a change, see [`Index::prepare`].
+pub struct Change {
+    removed: BTreeSet<String>,
+    added: BTreeSet<String>,
+}
+
+/// Entries of the tokens touched by a set of changes, updated in memory so that the changes can be
+/// stored in a single batch write.
+pub struct Index<'a> {
+    storage: &'a Storage,
+    tokens: BTreeMap<String, BTreeSet<u32>>,
+    /// Tokens whose entries were changed.
+    changed: BTreeSet<String>,
+}
+
+impl<'a> Index<'a> {
+    pub fn new(storage: &'a Storage) -> Self {
+        Index {
+            storage,
+            tokens: BTreeMap::new(),
+            changed: BTreeSet::new(),
+        }
+    }
+
+    /// Read the entries touched when a booking changes from `previous` to `next`.
+    ///
+    /// Reading is the only step which can fail, so it is separate from [`Index::apply`]: callers
+    /// can first check every other index, and then apply the change to all of them.
+    pub fn prepare(
+        &mut self,
+        previous: Option<&Booking>,
+        next: Option<&Booking>,
+    ) -> Result<Change, BookingError> {
+        let before = booking_tokens(previous);
+        let after = booking_tokens(next);
+        let change = Change {
+            removed: before.difference(&after).cloned().collect(),
+            added: after.difference(&before).cloned().collect(),
+        };
+        for token in change.removed.iter().chain(&change.added) {
+            if !self.tokens.contains_key(token) {
+                let ids = read(self.storage, token)?;
+                self.tokens.insert(token.clone(), ids.into_iter().collect());
+            }
+        }
+        Ok(change)
+    }
+
+    /// Record a change of the booking with ID `id` read by [`Index::prepare`].
+    pub fn apply(&mut self, id: u32, change: Change) {
+        for token in change.removed {
+            if let Some(ids) = self.tokens.get_mut(&token) {
+                ids.remove(&id);
+            }
+            self.changed.insert(token);
+        }
+        for token in change.added {
+            self.tokens.entry(token.clone()).or_default().insert(id);
+            self.changed.insert(token);
+        }
+    }
+
+    /// Keys of the changed tokens left without bookings, to [`prune`] once the change is stored.
+    pub fn emptied(&self) -> Vec<String> {
+        self.changed
+            .iter()
+            .filter(|token| self.tokens.get(*token).is_none_or(BTreeSet::is_empty))
+            .map(|token| self.storage.keys.search(token))
+            .collect()
+    }
+
+    /// Serialize the entries of every changed token into the key/value pairs to store.
+    ///
+    /// Entries left without bookings are stored empty, as a batch cannot delete keys.
+    pub fn encode(mut self) -> Result<Vec<(String, Vec<u8>)>, BookingError> {
+        self.changed
+            .iter()
+            .map(|token| {
+                let ids: Vec<u32> = self
+                    .tokens
+                    .remove(token)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .collect();
+                let bytes = serde_json::to_vec(&ids).map_err(|e| {
+                    BookingError::Storage(format!("failed to encode search index of {token}: {e}"))
+                })?;
+                Ok((self.storage.keys.search(token), bytes))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_split_at_non_alphanumeric_characters() {
+        let tokens: Vec<String> = tokens("Room 42, Müller-Lüdenscheid").into_iter().collect();
+        assert_eq!(tokens, ["42", "lüdenscheid", "müller", "room"]);
+    }
+
+    #[test]
+    fn tokens_skip_overlong_words() {
+        let long = "a".repeat(MAX_TOKEN_LEN + 1);
+        let fitting = "b".repeat(MAX_TOKEN_LEN);
+        let tokens = tokens(&format!("{long} {fitting}"));
+        assert_eq!(tokens.into_iter().collect::<Vec<_>>(), [fitting]);
+        assert_eq!(words(&long).count(), 1);
+    }
+}
@@ -0,0 +1,19 @@
+//! Helpers shared by the unit tests of several modules.
+
+use crate::exports::demo::booking_master::booking_management::{Booking, BookingStatus};
+
+/// A pending booking with the given subject, all other fields left at their defaults.
+pub fn booking(subject: &str) -> Booking {
+    Booking {
+        subject: subject.to_string(),
+        guest_name: String::new(),
+        resource: String::new(),
+        start: 0,
+        end: 0,
+        status: BookingStatus::Pending,
+        created_at: 0,
+        updated_at: 0,
+        version: 0,
+        expires_at: None,
+    }
+}
@@ -0,0 +1,204 @@
+//! Checks applied to the input of booking operations, whoever the caller is.
+//!
+//! All fields are checked, so that callers learn about every problem at once. Field paths name the
+//! parameter of the failing function, followed by the field of a record, e.g. `booking.subject`.
+
+use crate::exports::demo::booking_master::booking_management::{Booking, BookingError, FieldError};
+use crate::search;
+
+/// Maximum length of a subject, in characters.
+const MAX_SUBJECT_LEN: usize = 1024;
+/// Maximum length of a guest or resource name, in characters.
+const MAX_NAME_LEN: usize = 128;
+/// Maximum length of a tenant name, in characters.
+const MAX_TENANT_LEN: usize = 64;
+/// Maximum number of items of a batch operation.
+const MAX_BATCH_SIZE: usize = 100;
+
+/// Collects the fields failing validation.
+#[derive(Default)]
+struct Errors(Vec<FieldError>);
+
+impl Errors {
+    fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// Check a text field: it must fit `max_len` characters and not contain control characters.
+    fn text(&mut self, field: &str, value: &str, max_len: usize, required: bool) {
+        if required && value.trim().is_empty() {
+            self.add(field, "must not be empty");
+        } else if value.chars().count() > max_len {
+            self.add(field, format!("must be at most {max_len} characters long"));
+        } else if value.chars().any(char::is_control) {
+            self.add(field, "must not contain control characters");
+        }
+    }
+
+    fn range(&mut self, field: &str, start: u64, end: u64) {
+        if end <= start {
+            self.add(field, "must be after start");
+        }
+    }
+
+    fn finish(self) -> Result<(), BookingError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(BookingError::InvalidInput(self.0))
+        }
+    }
+}
+
+/// Check a booking passed as the `booking` parameter.
+///
+/// A booking reserving a resource must cover a non-empty time range.
+pub fn booking(booking: &Booking) -> Result<(), BookingError> {
+    let mut errors = Errors::default();
+    errors.text("booking.subject", &booking.subject, MAX_SUBJECT_LEN, true);
+    errors.text(
+        "booking.guest-name",
+        &booking.guest_name,
+        MAX_NAME_LEN,
+        false,
+    );
+    errors.text("booking.resource", &booking.resource, MAX_NAME_LEN, false);
+    if !booking.resource.is_empty() {
+        errors.range("booking.end", booking.start, booking.end);
+    }
+    errors.finish()
+}
+
+/// Check the `resource`, `start` and `end` parameters of an availability check.
+pub fn availability(resource: &str, start: u64, end: u64) -> Result<(), BookingError> {
+    let mut errors = Errors::default();
+    errors.text("resource", resource, MAX_NAME_LEN, true);
+    errors.range("end", start, end);
+    errors.finish()
+}
+
+/// Check the `query` parameter of a search, which must contain a word to search for. Longer words
+/// than indexed ones could never match, so they are rejected.
+pub fn query(query: &str) -> Result<(), BookingError> {
+    let mut errors = Errors::default();
+    errors.text("query", query, MAX_SUBJECT_LEN, true);
+    if errors.0.is_empty() {
+        let mut words = search::words(query).peekable();
+        if words.peek().is_none() {
+            errors.add("query", "must contain a word of letters or digits");
+        } else if words.any(|word| word.chars().count() > search::MAX_TOKEN_LEN) {
+            errors.add(
+                "query",
+                format!(
+                    "must only contain words of at most {} letters or digits",
+                    search::MAX_TOKEN_LEN
+                ),
+            );
+        }
+    }
+    errors.finish()
+}
+
+/// Check the number of items passed as the `field` parameter of a batch operation.
+pub fn batch(field: &str, len: usize) -> Result<(), BookingError> {
+    let mut errors = Errors::default();
+    if len > MAX_BATCH_SIZE {
+        errors.add(field, format!("must have at most {MAX_BATCH_SIZE} items"));
+    }
+    errors.finish()
+}
+
+/// Check the `tenant` parameter. Tenant names are part of keys, so they are restricted to
+/// characters which cannot be confused with the separators of the key layout.
+pub fn tenant(tenant: &str) -> Result<(), BookingError> {
+    let mut errors = Errors::default();
+    if tenant.len() > MAX_TENANT_LEN {
+        errors.add(
+            "tenant",
+            format!("must be at most {MAX_TENANT_LEN} characters long"),
+        );
+    } else if !tenant
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        errors.add("tenant", "must only contain ASCII letters, digits, - and _");
+    }
+    errors.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    /// The paths of the fields failing `result`.
+    fn fields(result: Result<(), BookingError>) -> Vec<String> {
+        match result {
+            Ok(()) => Vec::new(),
+            Err(BookingError::InvalidInput(errors)) => {
+                errors.into_iter().map(|e| e.field).collect()
+            }
+            Err(e) => panic!("unexpected error: {e:?}"),
+        }
+    }
+
+    #[test]
+    fn checks_booking_text_fields() {
+        assert!(fields(booking(&testing::booking("Room"))).is_empty());
+        let invalid = Booking {
+            guest_name: "a\nb".into(),
+            resource: "r".repeat(MAX_NAME_LEN + 1),
+            end: 1,
+            ..testing::booking(" ")
+        };
+        assert_eq!(
+            fields(booking(&invalid)),
+            ["booking.subject", "booking.guest-name", "booking.resource"]
+        );
+        let long = "ä".repeat(MAX_SUBJECT_LEN);
+        assert!(fields(booking(&testing::booking(&long))).is_empty());
+    }
+
+    #[test]
+    fn checks_time_ranges_of_reservations() {
+        let reserving = |start, end| Booking {
+            resource: "room-1".into(),
+            start,
+            end,
+            ..testing::booking("Room")
+        };
+        assert!(fields(booking(&reserving(10, 20))).is_empty());
+        assert_eq!(fields(booking(&reserving(10, 10))), ["booking.end"]);
+        assert!(fields(booking(&testing::booking("Room"))).is_empty());
+        assert!(fields(availability("room-1", 10, 20)).is_empty());
+        assert_eq!(fields(availability("", 20, 10)), ["resource", "end"]);
+    }
+
+    #[test]
+    fn restricts_tenant_names_to_key_safe_characters() {
+        assert!(fields(tenant("")).is_empty());
+        assert!(fields(tenant("acme_corp-2")).is_empty());
+        assert_eq!(fields(tenant("acme:corp")), ["tenant"]);
+        assert_eq!(fields(tenant("äcme")), ["tenant"]);
+        assert!(fields(tenant(&"a".repeat(MAX_TENANT_LEN))).is_empty());
+        assert_eq!(fields(tenant(&"a".repeat(MAX_TENANT_LEN + 1))), ["tenant"]);
+    }
+
+    #[test]
+    fn limits_batch_sizes() {
+        assert!(fields(batch("ids", MAX_BATCH_SIZE)).is_empty());
+        assert_eq!(fields(batch("ids", MAX_BATCH_SIZE + 1)), ["ids"]);
+    }
+
+    #[test]
+    fn query_needs_a_short_enough_word() {
+        assert!(fields(query("room 42")).is_empty());
+        assert_eq!(fields(query(" -- ")), ["query"]);
+        let long = "a".repeat(search::MAX_TOKEN_LEN + 1);
+        assert_eq!(fields(query(&format!("room {long}"))), ["query"]);
+        assert!(fields(query(&"a".repeat(search::MAX_TOKEN_LEN))).is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+//! Bearer token authentication and role-based authorization of the HTTP API.
+//!
+//! Requests carry a JWT in an `Authorization: Bearer <token>` header, signed with HS256 or RS256.
+//! The keys verifying them are provided through `wasi:config`:
+//!
+//! | Key                    | Default    | Description                                            |
+//! | ---------------------- | ---------- | ------------------------------------------------------ |
+//! | `auth`                 | `required` | `disabled` serves every request without a token        |
+//! | `jwt-hs256-secret`     | (none)     | Shared secret verifying HS256 tokens                   |
+//! | `jwt-rs256-public-key` | (none)     | PEM-encoded RSA public key verifying RS256 tokens      |
+//! | `jwt-issuer`           | (none)     | Value the `iss` claim must have, if set                |
+//! | `jwt-audience`         | (none)     | Value the `aud` claim must contain, if set             |
+//!
+//! Tokens signed with an algorithm no key is configured for are rejected, so with authentication
+//! required but no key configured every request is rejected.
+//!
+//! Tokens must carry an `exp` claim, and grant roles in a `roles` claim. Each role includes the
+//! ones before it: `reader` may `GET`, `writer` may also `POST` and `PUT`, and `admin` may also
+//! `DELETE`. Unknown roles are ignored. A `tenant` claim names the tenant the token acts for, the
+//! default tenant if absent.
+
+use core::fmt;
+
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use wasmcloud_component::http;
+
+use crate::wasi::config::store;
+use crate::{log, Level};
+
+/// Context of the audit log lines recording denied requests.
+const AUDIT_CONTEXT: &str = "demo.echo.audit";
+
+/// Permission granted to the bearer of a token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Reader,
+    Writer,
+    Admin,
+}
+
+impl Role {
+    /// Role required to make a request with the given method.
+    pub fn required_for(method: &http::Method) -> Role {
+        match *method {
+            http::Method::GET | http::Method::HEAD => Role::Reader,
+            http::Method::DELETE => Role::Admin,
+            _ => Role::Writer,
+        }
+    }
+
+    fn parse(name: &str) -> Option<Role> {
+        match name {
+            "reader" => Some(Role::Reader),
+            "writer" => Some(Role::Writer),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Role::Reader => "reader",
+            Role::Writer => "writer",
+            Role::Admin => "admin",
+        })
+    }
+}
+
+/// Claims read from a verified token.
+#[derive(Deserialize)]
+struct Claims {
+    #[serde(default)]
+    sub: String,
+    #[serde(default)]
+    roles: Vec<String>,
+    #[serde(default)]
+    tenant: String,
+}
+
+/// Caller authenticated by a token.
+pub struct Identity {
+    /// Subject of the token, empty if it names none.
+    pub subject: String,
+    /// Tenant the token acts for.
+    pub tenant: String,
+    /// Highest role granted by the token.
+    pub role: Role,
+}
+
+/// Reason a request is refused.
+pub enum Denial {
+    /// The request carries no valid token (401).
+    Unauthenticated(String),
+    /// The token does not permit the request (403).
+    Forbidden(String),
+    /// The configuration could not be read (503).
+    Unavailable(String),
+}
+
+/// Keys and constraints tokens are verified with.
+struct Settings {
+    hs256: Option<DecodingKey>,
+    rs256: Option<DecodingKey>,
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+impl Settings {
+    /// Read the settings, `None` if authentication is disabled.
+    fn load() -> Result<Option<Settings>, Denial> {
+        if get("auth")?.as_deref() == Some("disabled") {
+            return Ok(None);
+        }
+        let rs256 = get("jwt-rs256-public-key")?
+            .map(|pem| DecodingKey::from_rsa_pem(pem.as_bytes()))
+            .transpose()
+            .map_err(|e| Denial::Unavailable(format!("invalid jwt-rs256-public-key: {e}")))?;
+        Ok(Some(Settings {
+            hs256: get("jwt-hs256-secret")?
+                .map(|secret| DecodingKey::from_secret(secret.as_bytes())),
+            rs256,
+            issuer: get("jwt-issuer")?,
+            audience: get("jwt-audience")?,
+        }))
+    }
+
+    /// Verify `token`, returning its claims.
+    fn verify(&self, token: &str) -> Result<Claims, Denial> {
+        let invalid = |e: jsonwebtoken::errors::Error| {
+            Denial::Unauthenticated(format!("invalid bearer token: {e}"))
+        };
+        let algorithm = jsonwebtoken::decode_header(token).map_err(invalid)?.alg;
+        let key = match algorithm {
+            Algorithm::HS256 => self.hs256.as_ref(),
+            Algorithm::RS256 => self.rs256.as_ref(),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            Denial::Unauthenticated(format!("tokens signed with {algorithm:?} are not accepted"))
+        })?;
+        let mut validation = Validation::new(algorithm);
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        jsonwebtoken::decode::<Claims>(token, key, &validation)
+            .map(|data| data.claims)
+            .map_err(invalid)
+    }
+}
+
+/// Check that the caller may make a request with the given method and headers.
+///
+/// Returns the identity of the caller, or `None` if authentication is disabled.
+pub fn authorize(
+    method: &http::Method,
+    headers: &http::HeaderMap,
+) -> Result<Option<Identity>, Denial> {
+    let Some(settings) = Settings::load()? else {
+        return Ok(None);
+    };
+    let token = headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+        .map(|(_, token)| token.trim())
+        .ok_or_else(|| Denial::Unauthenticated("missing bearer token".to_string()))?;
+    let claims = settings.verify(token)?;
+    let Some(role) = claims
+        .roles
+        .iter()
+        .filter_map(|name| Role::parse(name))
+        .max()
+    else {
+        return Err(Denial::Forbidden(format!(
+            "subject {:?} holds no role",
+            claims.sub
+        )));
+    };
+    let identity = Identity {
+        subject: claims.sub,
+        tenant: claims.tenant,
+        role,
+    };
+    require(Some(&identity), Role::required_for(method))?;
+    Ok(Some(identity))
+}
+
+/// Check that the caller holds `role`, which always holds if authentication is disabled.
+pub fn require(identity: Option<&Identity>, role: Role) -> Result<(), Denial> {
+    match identity {
+        Some(identity) if identity.role < role => Err(Denial::Forbidden(format!(
+            "subject {:?} lacks the {role} role",
+            identity.subject
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Record a denied request in the audit log.
+pub fn audit(method: &http::Method, path: &str, denial: &Denial) {
+    let (status, reason) = match denial {
+        Denial::Unauthenticated(reason) => (401, reason),
+        Denial::Forbidden(reason) => (403, reason),
+        Denial::Unavailable(reason) => (503, reason),
+    };
+    log(
+        Level::Warn,
+        AUDIT_CONTEXT,
+        &format!("denied {method} {path} with status {status}: {reason}"),
+    );
+}
+
+fn get(key: &str) -> Result<Option<String>, Denial> {
+    store::get(key)
+        .map_err(|e| Denial::Unavailable(format!("failed to read configuration {key}: {e:?}")))
+}
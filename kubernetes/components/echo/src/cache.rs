@@ -0,0 +1,212 @@
+//! Read-through cache of the bookings returned by `GET /bookings/{id}`.
+//!
+//! A cached booking is served without calling the booking master, until its entry expires or the
+//! booking is changed through this component. Entries never outlive the expiry of their booking,
+//! so bookings removed by a sweep are not served from the cache either.
+//!
+//! | Key            | Default    | Description                                              |
+//! | -------------- | ---------- | -------------------------------------------------------- |
+//! | `cache`        | `disabled` | `memory` caches in the instance, `keyvalue` in a bucket  |
+//! | `cache-bucket` | (empty)    | Name of the key/value bucket holding the cached bookings |
+//! | `cache-ttl`    | `30`       | Seconds a booking is served from the cache for           |
+//!
+//! The `memory` cache is private to every instance of the component, so a change made through
+//! one instance is only seen by the others once their entries expire. The `keyvalue` cache is
+//! shared by all instances linked to the same bucket. Bookings changed by other clients of the
+//! booking master are seen once their entries expire in either case.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::BookingView;
+use crate::wasi::clocks::wall_clock;
+use crate::wasi::config::store as config;
+use crate::wasi::keyvalue::store;
+use crate::{log, Level, LOG_CONTEXT};
+
+/// Prefix of the keys holding cached bookings.
+const KEY_PREFIX: &str = "cache:";
+/// Seconds a booking is served from the cache for, unless configured otherwise.
+const DEFAULT_TTL: u64 = 30;
+/// Maximum number of bookings held by the `memory` cache of an instance.
+const MAX_MEMORY_ENTRIES: usize = 1024;
+
+thread_local! {
+    /// Entries of the `memory` cache, by key.
+    static MEMORY: RefCell<HashMap<String, Entry>> = RefCell::new(HashMap::new());
+}
+
+/// A cached booking.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    /// Time in seconds since the Unix epoch after which the entry is ignored.
+    expires_at: u64,
+    booking: BookingView,
+}
+
+/// Where cached bookings are held.
+enum Backend {
+    Memory,
+    KeyValue(store::Bucket),
+}
+
+/// The configured cache.
+pub struct Cache {
+    backend: Backend,
+    ttl: u64,
+}
+
+impl Cache {
+    /// Open the configured cache, `None` if caching is disabled or the cache is unavailable.
+    ///
+    /// The cache only saves calls to the booking master, so failures are logged and requests
+    /// are served without it.
+    pub fn open() -> Option<Cache> {
+        match Self::load() {
+            Ok(cache) => cache,
+            Err(e) => {
+                log(
+                    Level::Warn,
+                    LOG_CONTEXT,
+                    &format!("booking cache unavailable: {e}"),
+                );
+                None
+            }
+        }
+    }
+
+    fn load() -> Result<Option<Cache>, String> {
+        let get = |key: &str| {
+            config::get(key).map_err(|e| format!("failed to read configuration {key}: {e:?}"))
+        };
+        let backend = match get("cache")?.as_deref() {
+            None | Some("disabled") => return Ok(None),
+            Some("memory") => Backend::Memory,
+            Some("keyvalue") => Backend::KeyValue(
+                store::open(&get("cache-bucket")?.unwrap_or_default())
+                    .map_err(|e| format!("failed to open bucket: {e:?}"))?,
+            ),
+            Some(other) => return Err(format!("invalid cache {other:?}")),
+        };
+        let ttl = match get("cache-ttl")? {
+            Some(ttl) => ttl
+                .parse()
+                .map_err(|e| format!("invalid cache-ttl {ttl:?}: {e}"))?,
+            None => DEFAULT_TTL,
+        };
+        Ok(Some(Cache { backend, ttl }))
+    }
+
+    /// Return the cached booking of `tenant` with ID `id`, if there is one.
+    pub fn get(&self, tenant: &str, id: u32) -> Option<BookingView> {
+        let key = key(tenant, id);
+        let now = wall_clock::now().seconds;
+        match &self.backend {
+            Backend::Memory => MEMORY.with_borrow(|entries| {
+                entries
+                    .get(&key)
+                    .filter(|entry| entry.expires_at > now)
+                    .map(|entry| entry.booking.clone())
+            }),
+            Backend::KeyValue(bucket) => match read(bucket, &key) {
+                Ok(entry) => entry
+                    .filter(|entry| entry.expires_at > now)
+                    .map(|entry| entry.booking),
+                Err(e) => {
+                    log(
+                        Level::Warn,
+                        LOG_CONTEXT,
+                        &format!("booking cache failed: {e}"),
+                    );
+                    None
+                }
+            },
+        }
+    }
+
+    /// Cache `booking` as the booking of `tenant` with ID `id`.
+    pub fn put(&self, tenant: &str, id: u32, booking: BookingView) {
+        let now = wall_clock::now().seconds;
+        let expires_at = now
+            .saturating_add(self.ttl)
+            .min(booking.expires_at.unwrap_or(u64::MAX));
+        if expires_at <= now {
+            return;
+        }
+        let key = key(tenant, id);
+        let entry = Entry {
+            expires_at,
+            booking,
+        };
+        match &self.backend {
+            Backend::Memory => MEMORY.with_borrow_mut(|entries| {
+                if entries.len() >= MAX_MEMORY_ENTRIES {
+                    entries.retain(|_, entry| entry.expires_at > now);
+                }
+                if entries.len() >= MAX_MEMORY_ENTRIES {
+                    entries.clear();
+                }
+                entries.insert(key, entry);
+            }),
+            Backend::KeyValue(bucket) => {
+                if let Err(e) = write(bucket, &key, &entry) {
+                    log(
+                        Level::Warn,
+                        LOG_CONTEXT,
+                        &format!("booking cache failed: {e}"),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Drop the cached bookings of `tenant` with the given IDs, after they were changed.
+pub fn invalidate(tenant: &str, ids: &[u32]) {
+    let Some(cache) = Cache::open() else {
+        return;
+    };
+    for id in ids {
+        let key = key(tenant, *id);
+        match &cache.backend {
+            Backend::Memory => {
+                MEMORY.with_borrow_mut(|entries| entries.remove(&key));
+            }
+            Backend::KeyValue(bucket) => {
+                if let Err(e) = bucket.delete(&key) {
+                    // the entry still expires with its TTL
+                    log(
+                        Level::Warn,
+                        LOG_CONTEXT,
+                        &format!("failed to drop {key} from the booking cache: {e:?}"),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn key(tenant: &str, id: u32) -> String {
+    format!("{KEY_PREFIX}{tenant}:{id}")
+}
+
+fn read(bucket: &store::Bucket, key: &str) -> Result<Option<Entry>, String> {
+    let Some(bytes) = bucket
+        .get(key)
+        .map_err(|e| format!("failed to read {key}: {e:?}"))?
+    else {
+        return Ok(None);
+    };
+    serde_json::from_slice(&bytes)
+        .map(Some)
+        .map_err(|e| format!("failed to decode {key}: {e}"))
+}
+
+fn write(bucket: &store::Bucket, key: &str, entry: &Entry) -> Result<(), String> {
+    let bytes = serde_json::to_vec(entry).map_err(|e| format!("failed to encode {key}: {e}"))?;
+    bucket
+        .set(key, &bytes)
+        .map_err(|e| format!("failed to write {key}: {e:?}"))
+}
@@ -0,0 +1,114 @@
+//! Booking events published on the messaging bus as [CloudEvents 1.0] JSON documents.
+//!
+//! Every event is published on its own subject, `bookings.events.<kind>.<id>`, so subscribers can
+//! filter with wildcards, e.g. `bookings.events.created.*` or `bookings.events.*.42`.
+//!
+//! [CloudEvents 1.0]: https://github.com/cloudevents/spec/blob/v1.0.2/cloudevents/spec.md
+
+use serde::Serialize;
+use wasmcloud_component::HostRng;
+
+use crate::demo::booking_master::booking_management::Booking;
+use crate::model::BookingView;
+use crate::time;
+use crate::wasi::clocks::wall_clock;
+use crate::wasmcloud::messaging::{consumer, types};
+
+/// Prefix of the subjects booking events are published on.
+const SUBJECT_PREFIX: &str = "bookings.events";
+/// Value of the CloudEvents `source` attribute.
+const SOURCE: &str = "/demo/echo";
+
+/// What happened to a booking.
+#[derive(Debug, Clone, Copy)]
+pub enum EventKind {
+    Created,
+    Retrieved,
+    Updated,
+    Deleted,
+    Restored,
+    Expired,
+}
+
+impl EventKind {
+    fn name(&self) -> &'static str {
+        match self {
+            EventKind::Created => "created",
+            EventKind::Retrieved => "retrieved",
+            EventKind::Updated => "updated",
+            EventKind::Deleted => "deleted",
+            EventKind::Restored => "restored",
+            EventKind::Expired => "expired",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct CloudEvent<'a> {
+    specversion: &'static str,
+    id: String,
+    source: &'static str,
+    #[serde(rename = "type")]
+    ty: String,
+    subject: String,
+    /// Extension attribute naming the tenant owning the booking, absent for the default tenant.
+    #[serde(skip_serializing_if = "str::is_empty")]
+    tenant: &'a str,
+    time: String,
+    datacontenttype: &'static str,
+    data: EventData<'a>,
+}
+
+/// Payload carried in the `data` attribute of every booking event.
+#[derive(Serialize)]
+struct EventData<'a> {
+    id: u32,
+    booking: &'a BookingView,
+}
+
+/// Publish an event about the booking of `tenant` with ID `id`, identified by a fresh ID.
+pub fn publish(kind: EventKind, tenant: &str, id: u32, booking: &Booking) -> Result<(), String> {
+    publish_with_id(
+        kind,
+        tenant,
+        id,
+        booking,
+        HostRng::generate_guid().to_string(),
+        wall_clock::now().seconds,
+    )
+}
+
+/// Publish an event about the booking of `tenant` with ID `id`, identified by `event_id` and
+/// stamped with the time it occurred at.
+///
+/// Publishing the same event again uses the same ID, so consumers can discard duplicates.
+pub fn publish_with_id(
+    kind: EventKind,
+    tenant: &str,
+    id: u32,
+    booking: &Booking,
+    event_id: String,
+    occurred_at: u64,
+) -> Result<(), String> {
+    let booking = BookingView::from(booking.clone());
+    let event = CloudEvent {
+        specversion: "1.0",
+        id: event_id,
+        source: SOURCE,
+        ty: format!("demo.booking.{}", kind.name()),
+        subject: id.to_string(),
+        tenant,
+        time: time::rfc3339(occurred_at),
+        datacontenttype: "application/json",
+        data: EventData {
+            id,
+            booking: &booking,
+        },
+    };
+    let body = serde_json::to_vec(&event).map_err(|e| format!("failed to encode event: {e}"))?;
+    consumer::publish(&types::BrokerMessage {
+        subject: format!("{SUBJECT_PREFIX}.{}.{id}", kind.name()),
+        reply_to: None,
+        body,
+    })
+}
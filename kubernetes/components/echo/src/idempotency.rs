@@ -0,0 +1,254 @@
+//! Replay of responses to `POST` requests retried with the same `Idempotency-Key` header.
+//!
+//! The first request with a key is served as usual, and its response is stored in the key/value
+//! store together with a fingerprint of the request. Until the record expires, requests of the
+//! same tenant with the same key are answered with the stored response, without calling the
+//! booking master or publishing events again. The fingerprint covers the subject and role of the
+//! caller's token besides the method, path and body, so a key reused for a different request or by
+//! a different caller is answered with `422 Unprocessable Entity`.
+//!
+//! Responses with a `5xx` status are not stored, so a retry after a transient failure is served
+//! again. Neither are `401` and `403` responses, so a retry with a corrected token is served.
+//! Concurrent requests with the same key are not serialized: `wasi:keyvalue/store` cannot reserve
+//! a key atomically, so both may be served.
+//!
+//! | Key                  | Default | Description                                        |
+//! | -------------------- | ------- | -------------------------------------------------- |
+//! | `idempotency-bucket` | (empty) | Name of the key/value bucket holding the responses |
+//! | `idempotency-ttl`    | `86400` | Seconds a stored response is replayed for          |
+//!
+//! `wasi:keyvalue` cannot expire keys, so records carry their expiry time: expired records are
+//! ignored and replaced by the next request with their key.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use wasmcloud_component::http;
+
+use crate::auth::Identity;
+use crate::wasi::clocks::wall_clock;
+use crate::wasi::config::store as config;
+use crate::wasi::keyvalue::store;
+use crate::{log, response, Level, LOG_CONTEXT};
+
+/// Header carrying the idempotency key chosen by the client.
+pub const HEADER: &str = "idempotency-key";
+/// Header marking a replayed response.
+const REPLAYED_HEADER: &str = "idempotent-replayed";
+/// Prefix of the keys holding stored responses.
+const KEY_PREFIX: &str = "idempotency:";
+/// Maximum length of an idempotency key.
+const MAX_KEY_LENGTH: usize = 255;
+/// Seconds a stored response is replayed for, unless configured otherwise.
+const DEFAULT_TTL: u64 = 24 * 60 * 60;
+
+/// Response stored for an idempotency key.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    /// Fingerprint of the request the response was made to.
+    fingerprint: String,
+    /// Time in seconds since the Unix epoch after which the record is ignored.
+    expires_at: u64,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Record {
+    /// Rebuild the stored response, marked as replayed.
+    fn replay(self) -> http::Result<http::Response<String>> {
+        let mut builder = http::Response::builder()
+            .status(self.status)
+            .header(REPLAYED_HEADER, "true");
+        for (name, value) in self.headers {
+            builder = builder.header(name, value);
+        }
+        builder
+            .body(self.body)
+            .map_err(|e| http::ErrorCode::InternalError(Some(e.to_string())))
+    }
+}
+
+/// Serve `request` of `tenant` by `identity` carrying the idempotency key `key` with `handler`,
+/// unless a response to it is stored already.
+pub fn serve(
+    identity: Option<&Identity>,
+    tenant: &str,
+    key: &http::HeaderValue,
+    request: &http::IncomingRequest,
+    body: &[u8],
+    handler: impl FnOnce() -> http::Result<http::Response<String>>,
+) -> http::Result<http::Response<String>> {
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => key,
+        _ => {
+            return response::error(
+                http::StatusCode::BAD_REQUEST,
+                format!("Idempotency-Key must be 1 to {MAX_KEY_LENGTH} ASCII characters"),
+            );
+        }
+    };
+    let (bucket, ttl) = match open() {
+        Ok(opened) => opened,
+        Err(e) => return unavailable(e),
+    };
+    let stored_key = format!("{KEY_PREFIX}{tenant}:{key}");
+    let path = request
+        .uri()
+        .path_and_query()
+        .map_or("", |path| path.as_str());
+    let (subject, role) = identity.map_or((String::new(), String::new()), |identity| {
+        (identity.subject.clone(), identity.role.to_string())
+    });
+    let fingerprint = fingerprint(&[
+        tenant.as_bytes(),
+        subject.as_bytes(),
+        role.as_bytes(),
+        request.method().as_str().as_bytes(),
+        path.as_bytes(),
+        body,
+    ]);
+    let now = wall_clock::now().seconds;
+    match read(&bucket, &stored_key) {
+        Ok(Some(record)) if record.expires_at > now => {
+            if record.fingerprint != fingerprint {
+                return response::error(
+                    http::StatusCode::UNPROCESSABLE_ENTITY,
+                    "Idempotency-Key was used for a different request",
+                );
+            }
+            log(
+                Level::Info,
+                LOG_CONTEXT,
+                &format!("replayed response to idempotency key {key:?}"),
+            );
+            return record.replay();
+        }
+        Ok(_) => {}
+        Err(e) => return unavailable(e),
+    }
+    let response = handler()?;
+    if stored(response.status()) {
+        let record = Record {
+            fingerprint,
+            expires_at: now.saturating_add(ttl),
+            status: response.status().as_u16(),
+            headers: response
+                .headers()
+                .iter()
+                .map(|(name, value)| {
+                    let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                    (name.to_string(), value)
+                })
+                .collect(),
+            body: response.body().clone(),
+        };
+        // The request has been served, so a failure only means a retry is served again.
+        if let Err(e) = write(&bucket, &stored_key, &record) {
+            log(
+                Level::Warn,
+                LOG_CONTEXT,
+                &format!("failed to store response to idempotency key {key:?}: {e}"),
+            );
+        }
+    }
+    Ok(response)
+}
+
+/// Open the bucket holding stored responses, returning it with the configured TTL.
+fn open() -> Result<(store::Bucket, u64), String> {
+    let get = |key: &str| {
+        config::get(key).map_err(|e| format!("failed to read configuration {key}: {e:?}"))
+    };
+    let ttl = match get("idempotency-ttl")? {
+        Some(ttl) => ttl
+            .parse()
+            .map_err(|e| format!("invalid idempotency-ttl {ttl:?}: {e}"))?,
+        None => DEFAULT_TTL,
+    };
+    let bucket = store::open(&get("idempotency-bucket")?.unwrap_or_default())
+        .map_err(|e| format!("failed to open bucket: {e:?}"))?;
+    Ok((bucket, ttl))
+}
+
+/// Whether a response with `status` is stored for replay.
+fn stored(status: http::StatusCode) -> bool {
+    !status.is_server_error()
+        && status != http::StatusCode::UNAUTHORIZED
+        && status != http::StatusCode::FORBIDDEN
+}
+
+/// Hash the parts of a request a replayed response must match.
+fn fingerprint(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        // Length-prefix every part, so that moving bytes between parts changes the hash.
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn read(bucket: &store::Bucket, key: &str) -> Result<Option<Record>, String> {
+    let Some(bytes) = bucket
+        .get(key)
+        .map_err(|e| format!("failed to read {key}: {e:?}"))?
+    else {
+        return Ok(None);
+    };
+    serde_json::from_slice(&bytes)
+        .map(Some)
+        .map_err(|e| format!("failed to decode {key}: {e}"))
+}
+
+fn write(bucket: &store::Bucket, key: &str, record: &Record) -> Result<(), String> {
+    let bytes = serde_json::to_vec(record).map_err(|e| format!("failed to encode {key}: {e}"))?;
+    bucket
+        .set(key, &bytes)
+        .map_err(|e| format!("failed to write {key}: {e:?}"))
+}
+
+fn unavailable(reason: String) -> http::Result<http::Response<String>> {
+    log(
+        Level::Error,
+        LOG_CONTEXT,
+        &format!("idempotency store failed: {reason}"),
+    );
+    response::error(
+        http::StatusCode::SERVICE_UNAVAILABLE,
+        "idempotency store unavailable",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_separates_parts() {
+        assert_eq!(
+            fingerprint(&[b"alice", b"writer"]),
+            fingerprint(&[b"alice", b"writer"])
+        );
+        assert_ne!(
+            fingerprint(&[b"alice", b"writer"]),
+            fingerprint(&[b"alicew", b"riter"])
+        );
+        assert_ne!(
+            fingerprint(&[b"alice", b"writer"]),
+            fingerprint(&[b"bob", b"writer"])
+        );
+    }
+
+    #[test]
+    fn stores_neither_server_nor_auth_errors() {
+        assert!(stored(http::StatusCode::CREATED));
+        assert!(stored(http::StatusCode::CONFLICT));
+        assert!(!stored(http::StatusCode::UNAUTHORIZED));
+        assert!(!stored(http::StatusCode::FORBIDDEN));
+        assert!(!stored(http::StatusCode::SERVICE_UNAVAILABLE));
+    }
+}
@@ -1,114 +1,823 @@
-wit_bindgen::generate!({ generate_all });
+// The bindings cover the `messaging` world, so `export!` only exports the messaging handler and the
+// HTTP handler is left to `http::export!`.
+wit_bindgen::generate!({ world: "messaging", generate_all });
 
-use std::num::ParseIntError;
-
-use anyhow::{anyhow, Result};
+mod auth;
+mod cache;
+mod events;
+mod idempotency;
+mod maintenance;
+mod model;
+mod outbox;
+mod response;
+mod router;
+mod rpc;
+mod time;
+mod validation;
 
 use wasi::logging::logging::*;
-use wasmcloud::messaging::*;
 
 use wasmcloud_component::http;
 
-use crate::demo::booking_master::booking_management;
+use crate::auth::{Denial, Identity, Role};
+use crate::demo::booking_master::booking_management::{self, BookingError};
+use crate::events::EventKind;
+use crate::model::{
+    AvailabilityView, BatchItemView, BatchRequest, BatchView, BookingEntryView, BookingPageView,
+    BookingView, ErrorView, FieldErrorView, HealthView, HistoryView, NewBooking,
+};
+use crate::router::Route;
 
-const LOG_CONTEXT: &'static str = "demo.echo";
-const PUBLISH_SUBJECT: &'static str = "bookings.events";
+const LOG_CONTEXT: &str = "demo.echo";
+/// Header naming the tenant a request is made for.
+const TENANT_HEADER: &str = "x-tenant-id";
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct RequestBody {
-    booking: String,
+    booking: NewBooking,
+}
+
+/// Precondition placed on an update through the `If-Match` header.
+#[derive(Debug, PartialEq)]
+enum IfMatch {
+    Absent,
+    Any,
+    /// The booking versions named by the strong entity tags of the header.
+    Versions(Vec<u64>),
+    /// The header names no strong entity tag of a booking version, so it cannot match.
+    Invalid,
 }
 
 struct Component;
 
 impl Component {
-    fn parse_id(path: &str) -> Result<u32> {
-        path.split("/")
-            .last()
-            .ok_or(anyhow!("Failed to parse ID from path: {path}"))?
-            .parse()
-            .map_err(|e: ParseIntError| e.into())
+    /// Tenant a request is made for.
+    ///
+    /// Without authentication, this is the tenant named in the request headers, the default tenant
+    /// if there is none. The booking master validates tenant names, so invalid ones are passed on
+    /// unchanged. Otherwise, it is the tenant the token acts for, and the headers must not name
+    /// another one.
+    fn tenant(headers: &http::HeaderMap, identity: Option<&Identity>) -> Result<String, Denial> {
+        let named = headers
+            .get(TENANT_HEADER)
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned());
+        match (identity, named) {
+            (None, named) => Ok(named.unwrap_or_default()),
+            (Some(identity), Some(named)) if named != identity.tenant => {
+                Err(Denial::Forbidden(format!(
+                    "subject {:?} may not act for tenant {named:?}",
+                    identity.subject
+                )))
+            }
+            (Some(identity), _) => Ok(identity.tenant.clone()),
+        }
     }
-}
 
-impl http::Server for Component {
-    fn handle(
-        mut request: http::IncomingRequest,
-    ) -> http::Result<http::Response<impl http::OutgoingBody>> {
-        match request.method() {
-            &http::Method::GET => {
-                let path = request.uri().to_string();
-                let id = Self::parse_id(&path)
-                    .map_err(|e| http::ErrorCode::InternalError(Some(e.to_string())))?;
-                let booking = booking_management::get_booking(id)
-                    .map_err(|e| http::ErrorCode::InternalError(Some(e.into())))?;
-                consumer::publish(&types::BrokerMessage {
-                    subject: PUBLISH_SUBJECT.into(),
-                    reply_to: None,
-                    body: format!("Retrieved booking {id}: {booking}").into(),
-                })
-                .map_err(|e| http::ErrorCode::InternalError(Some(e.into())))?;
-                log(
-                    Level::Info,
-                    LOG_CONTEXT,
-                    &format!("handled GET request for ID {id}"),
+    /// Entity tag representing the given booking version.
+    fn etag(version: u64) -> String {
+        format!("\"{version}\"")
+    }
+
+    /// Read the `If-Match` precondition of a request.
+    ///
+    /// `If-Match` uses the strong comparison (RFC 9110, section 13.1.1), so weak entity tags in
+    /// the list never match.
+    fn if_match(headers: &http::HeaderMap) -> IfMatch {
+        let Some(value) = headers.get(http::header::IF_MATCH) else {
+            return IfMatch::Absent;
+        };
+        let Ok(value) = value.to_str() else {
+            return IfMatch::Invalid;
+        };
+        if value.trim() == "*" {
+            return IfMatch::Any;
+        }
+        let versions: Vec<u64> = value
+            .split(',')
+            .filter_map(|tag| {
+                tag.trim()
+                    .strip_prefix('"')?
+                    .strip_suffix('"')?
+                    .parse()
+                    .ok()
+            })
+            .collect();
+        if versions.is_empty() {
+            IfMatch::Invalid
+        } else {
+            IfMatch::Versions(versions)
+        }
+    }
+
+    /// Return the value of the query parameter `name`, if present and not empty.
+    fn query_param<'a>(query: Option<&'a str>, name: &str) -> Option<&'a str> {
+        query?
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+            .filter(|value| !value.is_empty())
+    }
+
+    /// Parse the booking carried in a request body, listing the fields failing validation
+    /// otherwise.
+    fn read_booking(body: &[u8]) -> Result<NewBooking, Vec<FieldErrorView>> {
+        validation::parse(body, validation::BOOKING_REQUEST).map(|body: RequestBody| body.booking)
+    }
+
+    /// Path of the booking with the given ID.
+    fn location(id: u32) -> String {
+        format!("/bookings/{id}")
+    }
+
+    /// Whether the `If-None-Match` header of a request matches the given booking version, so
+    /// that the client's copy of the booking is current.
+    fn if_none_match(headers: &http::HeaderMap, version: u64) -> bool {
+        let Some(Ok(value)) = headers.get(http::header::IF_NONE_MATCH).map(|v| v.to_str()) else {
+            return false;
+        };
+        value.split(',').map(str::trim).any(|tag| {
+            tag == "*" || tag.trim_start_matches("W/").trim_matches('"') == version.to_string()
+        })
+    }
+
+    /// Respond with a booking, its entity tag and the time it was last modified.
+    fn booking_response(
+        status: http::StatusCode,
+        booking: booking_management::Booking,
+    ) -> http::Result<http::Response<String>> {
+        let builder = http::Response::builder()
+            .status(status)
+            .header(http::header::ETAG, Self::etag(booking.version))
+            .header(
+                http::header::LAST_MODIFIED,
+                time::http_date(booking.updated_at),
+            );
+        response::json(builder, &BookingView::from(booking))
+    }
+
+    /// Publish the events recorded by the last write.
+    ///
+    /// The write itself succeeded, so failures are only logged: the events stay in the outbox and
+    /// go out with the next write.
+    fn relay_events() {
+        if let Err(e) = outbox::drain() {
+            log(
+                Level::Warn,
+                LOG_CONTEXT,
+                &format!("failed to relay booking events: {e}"),
+            );
+        }
+    }
+
+    /// Handle `GET /healthz`.
+    fn health() -> http::Result<http::Response<String>> {
+        response::json(
+            http::Response::builder(),
+            &HealthView {
+                status: "ok".into(),
+            },
+        )
+    }
+
+    /// Handle `GET /bookings?cursor=&limit=`, returning a page of bookings.
+    fn list_bookings(tenant: &str, query: Option<&str>) -> http::Result<http::Response<String>> {
+        if let Some(text) = Self::query_param(query, "q") {
+            return Self::search_bookings(tenant, text, query);
+        }
+        let cursor = Self::query_param(query, "cursor").map(str::parse::<u64>);
+        let limit = Self::query_param(query, "limit").map(str::parse::<u32>);
+        let (cursor, limit) = match (cursor.transpose(), limit.transpose()) {
+            (Ok(cursor), Ok(limit)) => (cursor, limit.unwrap_or_default()),
+            (Err(e), _) | (_, Err(e)) => {
+                return response::error(
+                    http::StatusCode::BAD_REQUEST,
+                    format!("invalid pagination parameters: {e}"),
+                );
+            }
+        };
+        let page = match booking_management::list_bookings(tenant, cursor, limit) {
+            Ok(page) => page,
+            Err(e) => return response::booking_error(e),
+        };
+        log(
+            Level::Info,
+            LOG_CONTEXT,
+            &format!(
+                "handled GET request listing {} bookings",
+                page.bookings.len()
+            ),
+        );
+        response::json(http::Response::builder(), &BookingPageView::from(page))
+    }
+
+    /// Handle `GET /bookings?q=&limit=`, returning the bookings matching the search query `text`.
+    fn search_bookings(
+        tenant: &str,
+        text: &str,
+        query: Option<&str>,
+    ) -> http::Result<http::Response<String>> {
+        if Self::query_param(query, "cursor").is_some() {
+            return response::error(
+                http::StatusCode::BAD_REQUEST,
+                "cursor cannot be combined with q",
+            );
+        }
+        let Some(text) = router::decode_query(text) else {
+            return response::error(http::StatusCode::BAD_REQUEST, "invalid search query");
+        };
+        let limit = match Self::query_param(query, "limit").map(str::parse::<u32>) {
+            Some(Ok(limit)) => limit,
+            None => 0,
+            Some(Err(e)) => {
+                return response::error(
+                    http::StatusCode::BAD_REQUEST,
+                    format!("invalid pagination parameters: {e}"),
+                );
+            }
+        };
+        let bookings = match booking_management::search_bookings(tenant, &text, limit) {
+            Ok(bookings) => bookings,
+            Err(e) => return response::booking_error(e),
+        };
+        log(
+            Level::Info,
+            LOG_CONTEXT,
+            &format!("handled GET request finding {} bookings", bookings.len()),
+        );
+        response::json(
+            http::Response::builder(),
+            &BookingPageView {
+                bookings: bookings.into_iter().map(BookingEntryView::from).collect(),
+                next_cursor: None,
+            },
+        )
+    }
+
+    /// Handle `GET /resources/{resource}/availability?start=&end=`.
+    fn check_availability(
+        tenant: &str,
+        resource: String,
+        query: Option<&str>,
+    ) -> http::Result<http::Response<String>> {
+        let start = Self::query_param(query, "start").map(str::parse::<u64>);
+        let end = Self::query_param(query, "end").map(str::parse::<u64>);
+        let (start, end) = match (start, end) {
+            (Some(Ok(start)), Some(Ok(end))) => (start, end),
+            (Some(Err(e)), _) | (_, Some(Err(e))) => {
+                return response::error(
+                    http::StatusCode::BAD_REQUEST,
+                    format!("invalid time range: {e}"),
                 );
-                Ok(http::Response::new(format!("Booking {id}: {booking}\n")))
-            }
-            &http::Method::POST => {
-                let path = request.uri().to_string();
-                let id = Self::parse_id(&path)
-                    .map_err(|e| http::ErrorCode::InternalError(Some(e.to_string())))?;
-                let subject: RequestBody = match serde_json::from_reader(request.body_mut()) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        return http::Response::builder()
-                            .status(http::StatusCode::BAD_REQUEST)
-                            .body(format!("invalid booking request: {e}").into())
-                            .map_err(|e| http::ErrorCode::InternalError(Some(e.to_string())));
+            }
+            _ => {
+                return response::error(
+                    http::StatusCode::BAD_REQUEST,
+                    "both start and end are required",
+                );
+            }
+        };
+        let conflicts = match booking_management::check_availability(tenant, &resource, start, end)
+        {
+            Ok(conflicts) => conflicts,
+            Err(e) => return response::booking_error(e),
+        };
+        log(
+            Level::Info,
+            LOG_CONTEXT,
+            &format!("handled GET request for availability of {resource}"),
+        );
+        response::json(
+            http::Response::builder(),
+            &AvailabilityView {
+                available: conflicts.is_empty(),
+                resource,
+                start,
+                end,
+                conflicts,
+            },
+        )
+    }
+
+    /// Handle `GET /bookings/{id}`, answering with `304 Not Modified` if the `If-None-Match`
+    /// header matches the current version.
+    fn get_booking(
+        tenant: &str,
+        id: u32,
+        headers: &http::HeaderMap,
+    ) -> http::Result<http::Response<String>> {
+        let cache = cache::Cache::open();
+        let booking = match cache.as_ref().and_then(|cache| cache.get(tenant, id)) {
+            Some(booking) => booking.into(),
+            None => match booking_management::get_booking(tenant, id) {
+                Ok(booking) => {
+                    if let Some(cache) = &cache {
+                        cache.put(tenant, id, booking.clone().into());
                     }
-                };
-                let booking = subject.booking;
-                booking_management::add_booking(id, &booking)
-                    .map_err(|e| http::ErrorCode::InternalError(Some(e.into())))?;
-                consumer::publish(&types::BrokerMessage {
-                    subject: PUBLISH_SUBJECT.into(),
-                    reply_to: None,
-                    body: format!("Created booking {id}: {booking}").into(),
-                })
-                .map_err(|e| http::ErrorCode::InternalError(Some(e.into())))?;
+                    booking
+                }
+                Err(e) => return response::booking_error(e),
+            },
+        };
+        if let Err(e) = events::publish(EventKind::Retrieved, tenant, id, &booking) {
+            log(
+                Level::Warn,
+                LOG_CONTEXT,
+                &format!("failed to publish retrieval of booking {id}: {e}"),
+            );
+        }
+        log(
+            Level::Info,
+            LOG_CONTEXT,
+            &format!("handled GET request for ID {id}"),
+        );
+        if Self::if_none_match(headers, booking.version) {
+            return http::Response::builder()
+                .status(http::StatusCode::NOT_MODIFIED)
+                .header(http::header::ETAG, Self::etag(booking.version))
+                .header(
+                    http::header::LAST_MODIFIED,
+                    time::http_date(booking.updated_at),
+                )
+                .body(String::new())
+                .map_err(|e| http::ErrorCode::InternalError(Some(e.to_string())));
+        }
+        Self::booking_response(http::StatusCode::OK, booking)
+    }
+
+    /// Handle `POST /bookings/{id}`.
+    fn add_booking(
+        tenant: &str,
+        id: u32,
+        booking: NewBooking,
+    ) -> http::Result<http::Response<String>> {
+        let booking = match booking_management::add_booking(tenant, id, &booking.into()) {
+            Ok(booking) => booking,
+            Err(e) => return response::booking_error(e),
+        };
+        Self::relay_events();
+        log(
+            Level::Info,
+            LOG_CONTEXT,
+            &format!("handled POST request for ID {id}"),
+        );
+        let builder = http::Response::builder()
+            .status(http::StatusCode::CREATED)
+            .header(http::header::LOCATION, Self::location(id))
+            .header(http::header::ETAG, Self::etag(booking.version));
+        response::json(builder, &BookingView::from(booking))
+    }
+
+    /// Handle `POST /bookings`, storing the booking under an ID allocated by the booking master.
+    fn create_booking(tenant: &str, booking: NewBooking) -> http::Result<http::Response<String>> {
+        let entry = match booking_management::create_booking(tenant, &booking.into()) {
+            Ok(entry) => entry,
+            Err(e) => return response::booking_error(e),
+        };
+        Self::relay_events();
+        log(
+            Level::Info,
+            LOG_CONTEXT,
+            &format!("handled POST request creating ID {}", entry.id),
+        );
+        let builder = http::Response::builder()
+            .status(http::StatusCode::CREATED)
+            .header(http::header::LOCATION, Self::location(entry.id))
+            .header(http::header::ETAG, Self::etag(entry.booking.version));
+        response::json(
+            builder,
+            &BookingEntryView {
+                id: entry.id,
+                booking: entry.booking.into(),
+            },
+        )
+    }
+
+    /// Decide the version an update of the booking with ID `id` expects under the `If-Match`
+    /// precondition, reading the current booking with `current` where the header does not name a
+    /// single version. Returns the expected version together with the status reporting a conflict,
+    /// or the status and body of the response rejecting the update.
+    ///
+    /// Updates must carry a precondition (`428`), and `*` only matches an existing booking (`412`).
+    /// With `*`, a conflict means a concurrent write won the race (`409`), whereas a failed
+    /// precondition naming versions is reported as such (`412`).
+    fn expected_version(
+        id: u32,
+        if_match: IfMatch,
+        current: impl FnOnce() -> Result<booking_management::Booking, BookingError>,
+    ) -> Result<(u64, http::StatusCode), (http::StatusCode, ErrorView)> {
+        let rejected = |status, message: String| {
+            (
+                status,
+                ErrorView {
+                    error: message,
+                    fields: Vec::new(),
+                },
+            )
+        };
+        let not_matched = || {
+            rejected(
+                http::StatusCode::PRECONDITION_FAILED,
+                format!("If-Match does not match booking {id}"),
+            )
+        };
+        match if_match {
+            IfMatch::Versions(versions) if versions.len() == 1 => {
+                Ok((versions[0], http::StatusCode::PRECONDITION_FAILED))
+            }
+            // with several candidates, the current version decides which one is expected
+            IfMatch::Versions(versions) => match current() {
+                Ok(current) if versions.contains(&current.version) => {
+                    Ok((current.version, http::StatusCode::PRECONDITION_FAILED))
+                }
+                Ok(_) => Err(not_matched()),
+                Err(e) => Err(response::booking_error_view(e)),
+            },
+            IfMatch::Invalid => Err(not_matched()),
+            IfMatch::Any => match current() {
+                Ok(current) => Ok((current.version, http::StatusCode::CONFLICT)),
+                Err(BookingError::NotFound) => Err(not_matched()),
+                Err(e) => Err(response::booking_error_view(e)),
+            },
+            IfMatch::Absent => Err(rejected(
+                http::StatusCode::PRECONDITION_REQUIRED,
+                "updates require an If-Match header".to_string(),
+            )),
+        }
+    }
+
+    /// Handle `PUT /bookings/{id}`.
+    fn update_booking(
+        tenant: &str,
+        id: u32,
+        headers: &http::HeaderMap,
+        body: &[u8],
+    ) -> http::Result<http::Response<String>> {
+        let if_match = Self::if_match(headers);
+        let booking = match Self::read_booking(body) {
+            Ok(booking) => booking.into(),
+            Err(e) => return response::invalid(e),
+        };
+        let (expected_version, conflict_status) = match Self::expected_version(id, if_match, || {
+            booking_management::get_booking(tenant, id)
+        }) {
+            Ok(expected) => expected,
+            Err((status, view)) => {
+                return response::json(http::Response::builder().status(status), &view);
+            }
+        };
+        let booking =
+            match booking_management::update_booking(tenant, id, &booking, expected_version) {
+                Ok(booking) => booking,
+                Err(BookingError::Conflict) => {
+                    return response::error(
+                        conflict_status,
+                        format!("booking {id} is no longer at version {expected_version}"),
+                    );
+                }
+                Err(e) => return response::booking_error(e),
+            };
+        cache::invalidate(tenant, &[id]);
+        Self::relay_events();
+        log(
+            Level::Info,
+            LOG_CONTEXT,
+            &format!("handled PUT request for ID {id}"),
+        );
+        Self::booking_response(http::StatusCode::OK, booking)
+    }
+
+    /// Handle `POST /bookings:batch`, adding, then reading, then deleting the listed bookings.
+    fn batch(tenant: &str, request: BatchRequest) -> http::Result<http::Response<String>> {
+        let add_ids: Vec<u32> = request.add.iter().map(|entry| entry.id).collect();
+        let bookings: Vec<_> = request
+            .add
+            .into_iter()
+            .map(|entry| (entry.id, entry.booking.into()))
+            .collect();
+        let added = if bookings.is_empty() {
+            Ok(Vec::new())
+        } else {
+            booking_management::add_bookings(tenant, &bookings)
+        };
+        let got = if request.get.is_empty() {
+            Ok(Vec::new())
+        } else {
+            booking_management::get_bookings(tenant, &request.get)
+        };
+        for (id, result) in request.get.iter().zip(got.iter().flatten()) {
+            let Ok(booking) = result else {
+                continue;
+            };
+            if let Err(e) = events::publish(EventKind::Retrieved, tenant, *id, booking) {
                 log(
-                    Level::Info,
+                    Level::Warn,
                     LOG_CONTEXT,
-                    &format!("handled POST request for ID {id}"),
+                    &format!("failed to publish retrieval of booking {id}: {e}"),
                 );
-                Ok(http::Response::new(format!("Booking {id} created\n")))
-            }
-            &http::Method::DELETE => {
-                let path = request.uri().to_string();
-                let id = Self::parse_id(&path)
-                    .map_err(|e| http::ErrorCode::InternalError(Some(e.to_string())))?;
-                booking_management::delete_booking(id)
-                    .map_err(|e| http::ErrorCode::InternalError(Some(e.into())))?;
-                consumer::publish(&types::BrokerMessage {
-                    subject: PUBLISH_SUBJECT.into(),
-                    reply_to: None,
-                    body: format!("Deleted booking {id}").into(),
+            }
+        }
+        let deleted = if request.delete.is_empty() {
+            Ok(Vec::new())
+        } else {
+            booking_management::delete_bookings(tenant, &request.delete)
+        };
+        cache::invalidate(tenant, &request.delete);
+        if !bookings.is_empty() || !request.delete.is_empty() {
+            Self::relay_events();
+        }
+        log(
+            Level::Info,
+            LOG_CONTEXT,
+            &format!(
+                "handled POST request for a batch of {} items",
+                add_ids.len() + request.get.len() + request.delete.len()
+            ),
+        );
+        response::json(
+            http::Response::builder(),
+            &BatchView {
+                add: Self::batch_items(&add_ids, added, http::StatusCode::CREATED, true),
+                get: Self::batch_items(&request.get, got, http::StatusCode::OK, true),
+                delete: Self::batch_items(
+                    &request.delete,
+                    deleted,
+                    http::StatusCode::NO_CONTENT,
+                    false,
+                ),
+            },
+        )
+    }
+
+    /// Describe the outcome of every item of a batch operation on the bookings with the given
+    /// IDs, reporting them with `status` when they succeeded.
+    fn batch_items(
+        ids: &[u32],
+        results: Result<Vec<Result<booking_management::Booking, BookingError>>, BookingError>,
+        status: http::StatusCode,
+        with_booking: bool,
+    ) -> Vec<BatchItemView> {
+        let results = match results {
+            Ok(results) => results,
+            Err(e) => {
+                // the operation failed as a whole, so every item failed for the same reason
+                let (status, error) = response::booking_error_view(e);
+                return ids
+                    .iter()
+                    .map(|id| BatchItemView {
+                        id: *id,
+                        status: status.as_u16(),
+                        booking: None,
+                        error: Some(error.clone()),
+                    })
+                    .collect();
+            }
+        };
+        ids.iter()
+            .zip(results)
+            .map(|(id, result)| match result {
+                Ok(booking) => BatchItemView {
+                    id: *id,
+                    status: status.as_u16(),
+                    booking: with_booking.then(|| booking.into()),
+                    error: None,
+                },
+                Err(e) => {
+                    let (status, error) = response::booking_error_view(e);
+                    BatchItemView {
+                        id: *id,
+                        status: status.as_u16(),
+                        booking: None,
+                        error: Some(error),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Handle `GET /bookings/{id}/history`.
+    fn booking_history(tenant: &str, id: u32) -> http::Result<http::Response<String>> {
+        let history = match booking_management::get_booking_history(tenant, id) {
+            Ok(history) => history,
+            Err(e) => return response::booking_error(e),
+        };
+        log(
+            Level::Info,
+            LOG_CONTEXT,
+            &format!("handled GET request for history of ID {id}"),
+        );
+        response::json(
+            http::Response::builder(),
+            &HistoryView {
+                id,
+                history: history.into_iter().map(Into::into).collect(),
+            },
+        )
+    }
+
+    /// Handle `POST /bookings/{id}/restore`.
+    fn restore_booking(tenant: &str, id: u32) -> http::Result<http::Response<String>> {
+        let booking = match booking_management::restore_booking(tenant, id) {
+            Ok(booking) => booking,
+            Err(e) => return response::booking_error(e),
+        };
+        cache::invalidate(tenant, &[id]);
+        Self::relay_events();
+        log(
+            Level::Info,
+            LOG_CONTEXT,
+            &format!("handled POST request restoring ID {id}"),
+        );
+        Self::booking_response(http::StatusCode::OK, booking)
+    }
+
+    /// Handle `DELETE /bookings/{id}`.
+    fn delete_booking(tenant: &str, id: u32) -> http::Result<http::Response<String>> {
+        if let Err(e) = booking_management::delete_booking(tenant, id) {
+            return response::booking_error(e);
+        }
+        cache::invalidate(tenant, &[id]);
+        Self::relay_events();
+        log(
+            Level::Info,
+            LOG_CONTEXT,
+            &format!("handled DELETE request for ID {id}"),
+        );
+        http::Response::builder()
+            .status(http::StatusCode::NO_CONTENT)
+            .body(String::new())
+            .map_err(|e| http::ErrorCode::InternalError(Some(e.to_string())))
+    }
+
+    /// Refuse a request, recording it in the audit log.
+    fn deny(
+        request: &http::IncomingRequest,
+        denial: Denial,
+    ) -> http::Result<http::Response<String>> {
+        auth::audit(request.method(), request.uri().path(), &denial);
+        response::denied(denial)
+    }
+
+    /// Serve a request for `route` made by `identity`, whose body has been read into `body`.
+    fn serve(
+        request: &http::IncomingRequest,
+        route: Route,
+        identity: Option<&Identity>,
+        tenant: &str,
+        body: &[u8],
+    ) -> http::Result<http::Response<String>> {
+        match (route, request.method()) {
+            (Route::Health, &http::Method::GET) => Self::health(),
+            (Route::Bookings, &http::Method::GET) => {
+                Self::list_bookings(tenant, request.uri().query())
+            }
+            (Route::Booking(id), &http::Method::GET) => {
+                Self::get_booking(tenant, id, request.headers())
+            }
+            (Route::Bookings, &http::Method::POST) => match Self::read_booking(body) {
+                Ok(booking) => Self::create_booking(tenant, booking),
+                Err(e) => response::invalid(e),
+            },
+            (Route::BookingsBatch, &http::Method::POST) => {
+                match validation::parse::<BatchRequest>(body, validation::BATCH_REQUEST) {
+                    // deleting takes the admin role, even when requested with `POST`
+                    Ok(batch) if !batch.delete.is_empty() => {
+                        match auth::require(identity, Role::Admin) {
+                            Ok(()) => Self::batch(tenant, batch),
+                            Err(denial) => Self::deny(request, denial),
+                        }
+                    }
+                    Ok(batch) => Self::batch(tenant, batch),
+                    Err(e) => response::invalid(e),
+                }
+            }
+            (Route::Booking(id), &http::Method::POST) => match Self::read_booking(body) {
+                Ok(booking) => Self::add_booking(tenant, id, booking),
+                Err(e) => response::invalid(e),
+            },
+            (Route::Booking(id), &http::Method::PUT) => {
+                Self::update_booking(tenant, id, request.headers(), body)
+            }
+            (Route::Booking(id), &http::Method::DELETE) => Self::delete_booking(tenant, id),
+            (Route::BookingHistory(id), &http::Method::GET) => Self::booking_history(tenant, id),
+            (Route::BookingRestore(id), &http::Method::POST) => Self::restore_booking(tenant, id),
+            (Route::Availability(resource), &http::Method::GET) => {
+                Self::check_availability(tenant, resource, request.uri().query())
+            }
+            (Route::NotFound, _) => response::error(
+                http::StatusCode::NOT_FOUND,
+                format!("no such resource: {}", request.uri().path()),
+            ),
+            (route, method) => {
+                let allow = route
+                    .allowed_methods()
+                    .iter()
+                    .map(http::Method::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                response::json(
+                    http::Response::builder()
+                        .status(http::StatusCode::METHOD_NOT_ALLOWED)
+                        .header(http::header::ALLOW, allow),
+                    &model::ErrorView {
+                        error: format!("method {method} not allowed"),
+                        fields: Vec::new(),
+                    },
+                )
+            }
+        }
+    }
+}
+
+impl http::Server for Component {
+    fn handle(
+        mut request: http::IncomingRequest,
+    ) -> http::Result<http::Response<impl http::OutgoingBody>> {
+        let route = Route::parse(request.uri().path());
+        // Health checks come from the platform, which holds no token.
+        let identity = match route {
+            Route::Health => Ok(None),
+            _ => auth::authorize(request.method(), request.headers()),
+        };
+        let (identity, tenant) = match identity.and_then(|identity| {
+            let tenant = Self::tenant(request.headers(), identity.as_ref())?;
+            Ok((identity, tenant))
+        }) {
+            Ok(authorized) => authorized,
+            Err(denial) => return Self::deny(&request, denial),
+        };
+        let (identity, tenant) = (identity.as_ref(), tenant.as_str());
+        let body = match validation::read_body(request.body_mut()) {
+            Ok(body) => body,
+            Err(e) => return response::invalid(e),
+        };
+        match request.headers().get(idempotency::HEADER) {
+            Some(key) if request.method() == http::Method::POST => {
+                idempotency::serve(identity, tenant, key, &request, &body, || {
+                    Self::serve(&request, route, identity, tenant, &body)
                 })
-                .map_err(|e| http::ErrorCode::InternalError(Some(e.into())))?;
-                log(
-                    Level::Info,
-                    LOG_CONTEXT,
-                    &format!("handled DELETE request for ID {id}"),
-                );
-                Ok(http::Response::new(format!("Deleted booking {id}\n")))
             }
-            m => Ok(http::Response::builder()
-                .status(http::StatusCode::BAD_REQUEST)
-                .body(format!("invalid HTTP method: {m}").into())
-                .map_err(|e| http::ErrorCode::InternalError(Some(e.to_string()))))?,
+            _ => Self::serve(&request, route, identity, tenant, &body),
         }
     }
 }
 
 http::export!(Component);
+export!(Component);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn if_match(value: &str) -> IfMatch {
+        let mut headers = http::HeaderMap::new();
+        headers.insert(http::header::IF_MATCH, value.parse().unwrap());
+        Component::if_match(&headers)
+    }
+
+    #[test]
+    fn if_match_reads_strong_tags() {
+        assert_eq!(
+            Component::if_match(&http::HeaderMap::new()),
+            IfMatch::Absent
+        );
+        assert_eq!(if_match("*"), IfMatch::Any);
+        assert_eq!(if_match("\"3\""), IfMatch::Versions(vec![3]));
+        assert_eq!(if_match("\"1\", \"2\""), IfMatch::Versions(vec![1, 2]));
+    }
+
+    #[test]
+    fn if_match_ignores_weak_and_malformed_tags() {
+        assert_eq!(if_match("W/\"3\""), IfMatch::Invalid);
+        assert_eq!(if_match("W/\"1\", \"2\""), IfMatch::Versions(vec![2]));
+        assert_eq!(if_match("3"), IfMatch::Invalid);
+        assert_eq!(if_match("\"abc\""), IfMatch::Invalid);
+    }
+
+    fn rejection(
+        if_match: IfMatch,
+        current: Result<booking_management::Booking, BookingError>,
+    ) -> http::StatusCode {
+        match Component::expected_version(42, if_match, || current) {
+            Ok(expected) => panic!("update accepted, expecting {expected:?}"),
+            Err((status, _)) => status,
+        }
+    }
+
+    #[test]
+    fn updates_require_a_precondition() {
+        assert_eq!(
+            rejection(IfMatch::Absent, Err(BookingError::NotFound)),
+            http::StatusCode::PRECONDITION_REQUIRED
+        );
+    }
+
+    #[test]
+    fn any_version_does_not_match_a_missing_booking() {
+        assert_eq!(
+            rejection(IfMatch::Any, Err(BookingError::NotFound)),
+            http::StatusCode::PRECONDITION_FAILED
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Maintenance tasks triggered over the messaging bus, e.g. by a cron job.
+//!
+//! A message on `bookings.maintenance.sweep` archives and removes the bookings of all tenants
+//! whose expiry has passed, at most 100 per message, and publishes a `demo.booking.expired` event
+//! for each. Events which were pending already are relayed as well. If the message has a reply
+//! subject, the number of removed bookings is sent back as `{"expired": 3}`.
+//!
+//! A message on `bookings.maintenance.reconcile` repairs the indexes of the booking master, which
+//! lists every key of the bucket to do so, and relays the events it lists again. The number of
+//! added index entries is sent back as `{"added": 3}`.
+
+use serde::Serialize;
+
+use crate::demo::booking_master::booking_management;
+use crate::{log, Component, Level, LOG_CONTEXT};
+
+/// Subject triggering a sweep of expired bookings.
+pub const SWEEP_SUBJECT: &str = "bookings.maintenance.sweep";
+
+/// Subject triggering a reconciliation of the indexes of the booking master.
+pub const RECONCILE_SUBJECT: &str = "bookings.maintenance.reconcile";
+
+/// Reply to a sweep.
+#[derive(Serialize)]
+struct SweepView {
+    expired: u32,
+}
+
+/// Sweep expired bookings, returning the body of the reply.
+pub fn sweep() -> Result<Vec<u8>, String> {
+    let expired = booking_management::sweep_expired(0)
+        .map_err(|e| format!("failed to sweep expired bookings: {e:?}"))?;
+    Component::relay_events();
+    log(
+        Level::Info,
+        LOG_CONTEXT,
+        &format!("swept {expired} expired bookings"),
+    );
+    serde_json::to_vec(&SweepView { expired }).map_err(|e| format!("failed to encode reply: {e}"))
+}
+
+/// Reply to a reconciliation.
+#[derive(Serialize)]
+struct ReconcileView {
+    added: u32,
+}
+
+/// Reconcile the indexes of the booking master, returning the body of the reply.
+pub fn reconcile() -> Result<Vec<u8>, String> {
+    let added = booking_management::reconcile_indexes()
+        .map_err(|e| format!("failed to reconcile indexes: {e:?}"))?;
+    // pending events missing from the outbox index are listed again
+    Component::relay_events();
+    log(
+        Level::Info,
+        LOG_CONTEXT,
+        &format!("added {added} missing index entries"),
+    );
+    serde_json::to_vec(&ReconcileView { added }).map_err(|e| format!("failed to encode reply: {e}"))
+}
+
+/// Run the maintenance task triggered by a message on `subject`, returning the body of the reply,
+/// or `None` if `subject` triggers no task.
+pub fn run(subject: &str) -> Option<Result<Vec<u8>, String>> {
+    match subject {
+        SWEEP_SUBJECT => Some(sweep()),
+        RECONCILE_SUBJECT => Some(reconcile()),
+        _ => None,
+    }
+}
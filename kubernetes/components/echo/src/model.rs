@@ -0,0 +1,296 @@
+//! JSON representations of the booking types exchanged over the HTTP API.
+
+use serde::{Deserialize, Serialize};
+
+use crate::demo::booking_master::booking_management::{
+    Booking, BookingEntry, BookingPage, BookingStatus, EventKind, FieldError, HistoryEntry,
+};
+
+/// Booking fields provided by a client when creating a booking.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NewBooking {
+    pub subject: String,
+    #[serde(default)]
+    pub guest_name: String,
+    #[serde(default)]
+    pub resource: String,
+    #[serde(default)]
+    pub start: u64,
+    #[serde(default)]
+    pub end: u64,
+    #[serde(default)]
+    pub status: Status,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+/// A booking as returned to clients.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookingView {
+    pub subject: String,
+    pub guest_name: String,
+    pub resource: String,
+    pub start: u64,
+    pub end: u64,
+    pub status: Status,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub version: u64,
+    /// Time after which the booking is archived and removed, absent if it never expires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pending,
+    #[default]
+    Confirmed,
+    Cancelled,
+}
+
+impl From<NewBooking> for Booking {
+    fn from(booking: NewBooking) -> Self {
+        Booking {
+            subject: booking.subject,
+            guest_name: booking.guest_name,
+            resource: booking.resource,
+            start: booking.start,
+            end: booking.end,
+            status: booking.status.into(),
+            // managed by the booking master
+            created_at: 0,
+            updated_at: 0,
+            version: 0,
+            expires_at: booking.expires_at,
+        }
+    }
+}
+
+impl From<Booking> for BookingView {
+    fn from(booking: Booking) -> Self {
+        BookingView {
+            subject: booking.subject,
+            guest_name: booking.guest_name,
+            resource: booking.resource,
+            start: booking.start,
+            end: booking.end,
+            status: booking.status.into(),
+            created_at: booking.created_at,
+            updated_at: booking.updated_at,
+            version: booking.version,
+            expires_at: booking.expires_at,
+        }
+    }
+}
+
+impl From<BookingView> for Booking {
+    fn from(booking: BookingView) -> Self {
+        Booking {
+            subject: booking.subject,
+            guest_name: booking.guest_name,
+            resource: booking.resource,
+            start: booking.start,
+            end: booking.end,
+            status: booking.status.into(),
+            created_at: booking.created_at,
+            updated_at: booking.updated_at,
+            version: booking.version,
+            expires_at: booking.expires_at,
+        }
+    }
+}
+
+impl From<Status> for BookingStatus {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Pending => BookingStatus::Pending,
+            Status::Confirmed => BookingStatus::Confirmed,
+            Status::Cancelled => BookingStatus::Cancelled,
+        }
+    }
+}
+
+impl From<BookingStatus> for Status {
+    fn from(status: BookingStatus) -> Self {
+        match status {
+            BookingStatus::Pending => Status::Pending,
+            BookingStatus::Confirmed => Status::Confirmed,
+            BookingStatus::Cancelled => Status::Cancelled,
+        }
+    }
+}
+
+/// A booking together with its identifier, as returned in listings.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BookingEntryView {
+    pub id: u32,
+    pub booking: BookingView,
+}
+
+impl From<BookingEntry> for BookingEntryView {
+    fn from(entry: BookingEntry) -> Self {
+        BookingEntryView {
+            id: entry.id,
+            booking: entry.booking.into(),
+        }
+    }
+}
+
+/// A page of bookings as returned by `GET /bookings`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BookingPageView {
+    pub bookings: Vec<BookingEntryView>,
+    pub next_cursor: Option<u64>,
+}
+
+impl From<BookingPage> for BookingPageView {
+    fn from(page: BookingPage) -> Self {
+        BookingPageView {
+            bookings: page
+                .bookings
+                .into_iter()
+                .map(BookingEntryView::from)
+                .collect(),
+            next_cursor: page.next_cursor,
+        }
+    }
+}
+
+/// A booking to create under the given ID, as listed in a batch request.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NewBookingEntry {
+    pub id: u32,
+    pub booking: NewBooking,
+}
+
+/// Body of a `POST /bookings:batch` request. Every operation is optional.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchRequest {
+    #[serde(default)]
+    pub add: Vec<NewBookingEntry>,
+    #[serde(default)]
+    pub get: Vec<u32>,
+    #[serde(default)]
+    pub delete: Vec<u32>,
+}
+
+/// Outcome of one item of a batch request.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchItemView {
+    pub id: u32,
+    /// Status the item would have been answered with as a request of its own.
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub booking: Option<BookingView>,
+    #[serde(flatten)]
+    pub error: Option<ErrorView>,
+}
+
+/// Response to `POST /bookings:batch`, listing the outcome of every item in the order of the
+/// request.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchView {
+    pub add: Vec<BatchItemView>,
+    pub get: Vec<BatchItemView>,
+    pub delete: Vec<BatchItemView>,
+}
+
+/// A change recorded in the history of a booking.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryEntryView {
+    pub kind: ChangeKind,
+    pub booking: BookingView,
+    pub occurred_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    Deleted,
+    Restored,
+    Expired,
+}
+
+/// The history of a booking as returned by `GET /bookings/{id}/history`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryView {
+    pub id: u32,
+    pub history: Vec<HistoryEntryView>,
+}
+
+impl From<HistoryEntry> for HistoryEntryView {
+    fn from(entry: HistoryEntry) -> Self {
+        HistoryEntryView {
+            kind: match entry.kind {
+                EventKind::Created => ChangeKind::Created,
+                EventKind::Updated => ChangeKind::Updated,
+                EventKind::Deleted => ChangeKind::Deleted,
+                EventKind::Restored => ChangeKind::Restored,
+                EventKind::Expired => ChangeKind::Expired,
+            },
+            booking: entry.booking.into(),
+            occurred_at: entry.occurred_at,
+        }
+    }
+}
+
+/// Availability of a resource as returned by `GET /resources/{resource}/availability`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AvailabilityView {
+    pub resource: String,
+    pub start: u64,
+    pub end: u64,
+    pub available: bool,
+    /// IDs of the bookings reserving the resource during the requested time range.
+    pub conflicts: Vec<u32>,
+}
+
+/// Body of every error response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorView {
+    pub error: String,
+    /// The fields failing validation, if the request was rejected because of them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldErrorView>,
+}
+
+/// A field of a request failing validation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldErrorView {
+    /// Path of the field, e.g. `booking.subject`.
+    pub field: String,
+    pub message: String,
+}
+
+impl From<FieldError> for FieldErrorView {
+    fn from(error: FieldError) -> Self {
+        FieldErrorView {
+            // the booking master names fields as in WIT, i.e. in kebab case
+            field: error.field.replace('-', "_"),
+            message: error.message,
+        }
+    }
+}
+
+/// Body of the `GET /healthz` response.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthView {
+    pub status: String,
+}
+
+/// Reply to a request received over the messaging bus.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplyView {
+    /// HTTP status code of the matching HTTP response.
+    pub status: u16,
+    /// Body of the matching HTTP response, absent if it has none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<serde_json::Value>,
+}
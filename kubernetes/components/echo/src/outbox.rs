@@ -0,0 +1,63 @@
+//! Relay publishing the booking events recorded in the outbox of the booking master.
+//!
+//! The booking master stores an event together with every change to a booking. After each write,
+//! the relay publishes the pending events and acknowledges them. An event whose acknowledgement
+//! fails is published again by a later relay run, with the same CloudEvents `id`.
+
+use crate::demo::booking_master::booking_management::{self, BookingError};
+use crate::events::{self, EventKind};
+
+/// Maximum number of events fetched from the outbox at once.
+const BATCH_SIZE: u32 = 100;
+
+impl From<booking_management::EventKind> for EventKind {
+    fn from(kind: booking_management::EventKind) -> Self {
+        match kind {
+            booking_management::EventKind::Created => EventKind::Created,
+            booking_management::EventKind::Updated => EventKind::Updated,
+            booking_management::EventKind::Deleted => EventKind::Deleted,
+            booking_management::EventKind::Restored => EventKind::Restored,
+            booking_management::EventKind::Expired => EventKind::Expired,
+        }
+    }
+}
+
+/// Publish and acknowledge all pending events, oldest first.
+///
+/// Stops at the first event that cannot be published, so that events about a booking are never
+/// published out of order. Returns the number of published events.
+pub fn drain() -> Result<usize, String> {
+    let mut published = 0;
+    loop {
+        let pending = booking_management::pending_events(BATCH_SIZE)
+            .map_err(|e| format!("failed to read outbox: {}", describe(e)))?;
+        if pending.is_empty() {
+            return Ok(published);
+        }
+        let full = pending.len() >= BATCH_SIZE as usize;
+        for event in pending {
+            events::publish_with_id(
+                event.kind.into(),
+                &event.tenant,
+                event.booking_id,
+                &event.booking,
+                event.key.clone(),
+                event.occurred_at,
+            )?;
+            booking_management::ack_event(&event.key).map_err(|e| {
+                format!("failed to acknowledge event {}: {}", event.key, describe(e))
+            })?;
+            published += 1;
+        }
+        if !full {
+            return Ok(published);
+        }
+    }
+}
+
+fn describe(error: BookingError) -> String {
+    match error {
+        BookingError::Storage(reason) => reason,
+        e => format!("{e:?}"),
+    }
+}
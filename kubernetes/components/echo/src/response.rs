@@ -0,0 +1,130 @@
+//! Helpers building the JSON responses of the HTTP API.
+
+use serde::Serialize;
+use wasmcloud_component::http;
+
+use crate::auth::Denial;
+use crate::demo::booking_master::booking_management::BookingError;
+use crate::model::{ErrorView, FieldErrorView};
+use crate::{log, Level, LOG_CONTEXT};
+
+/// Finish `builder` with `value` serialized as JSON body.
+pub fn json<T: Serialize>(
+    builder: http::response::Builder,
+    value: &T,
+) -> http::Result<http::Response<String>> {
+    let body = serde_json::to_string(value)
+        .map_err(|e| http::ErrorCode::InternalError(Some(e.to_string())))?;
+    builder
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .map_err(|e| http::ErrorCode::InternalError(Some(e.to_string())))
+}
+
+/// Build an error response with the given status and message.
+pub fn error(
+    status: http::StatusCode,
+    message: impl Into<String>,
+) -> http::Result<http::Response<String>> {
+    json(
+        http::Response::builder().status(status),
+        &ErrorView {
+            error: message.into(),
+            fields: Vec::new(),
+        },
+    )
+}
+
+/// Build a `400 Bad Request` response listing the fields failing validation.
+pub fn invalid(fields: Vec<FieldErrorView>) -> http::Result<http::Response<String>> {
+    json(
+        http::Response::builder().status(http::StatusCode::BAD_REQUEST),
+        &ErrorView {
+            error: "invalid request".to_string(),
+            fields,
+        },
+    )
+}
+
+/// Build the response refusing a request.
+pub fn denied(denial: Denial) -> http::Result<http::Response<String>> {
+    match denial {
+        Denial::Unauthenticated(reason) => json(
+            http::Response::builder()
+                .status(http::StatusCode::UNAUTHORIZED)
+                .header(http::header::WWW_AUTHENTICATE, "Bearer"),
+            &ErrorView {
+                error: reason,
+                fields: Vec::new(),
+            },
+        ),
+        Denial::Forbidden(reason) => self::error(http::StatusCode::FORBIDDEN, reason),
+        Denial::Unavailable(reason) => {
+            log(
+                Level::Error,
+                LOG_CONTEXT,
+                &format!("authentication failed: {reason}"),
+            );
+            self::error(
+                http::StatusCode::SERVICE_UNAVAILABLE,
+                "authentication unavailable",
+            )
+        }
+    }
+}
+
+/// Turn a failed booking operation into the matching error response.
+pub fn booking_error(error: BookingError) -> http::Result<http::Response<String>> {
+    let (status, view) = booking_error_view(error);
+    json(http::Response::builder().status(status), &view)
+}
+
+/// Status and body of the error response matching a failed booking operation.
+pub fn booking_error_view(error: BookingError) -> (http::StatusCode, ErrorView) {
+    let (status, message, fields) = match error {
+        BookingError::NotFound => (
+            http::StatusCode::NOT_FOUND,
+            "booking not found".to_string(),
+            Vec::new(),
+        ),
+        BookingError::AlreadyExists => (
+            http::StatusCode::CONFLICT,
+            "booking already exists".to_string(),
+            Vec::new(),
+        ),
+        BookingError::InvalidInput(fields) => (
+            http::StatusCode::BAD_REQUEST,
+            "invalid request".to_string(),
+            fields.into_iter().map(FieldErrorView::from).collect(),
+        ),
+        BookingError::Storage(reason) => {
+            log(
+                Level::Error,
+                LOG_CONTEXT,
+                &format!("booking storage failed: {reason}"),
+            );
+            (
+                http::StatusCode::SERVICE_UNAVAILABLE,
+                "booking storage unavailable".to_string(),
+                Vec::new(),
+            )
+        }
+        BookingError::Conflict => (
+            http::StatusCode::CONFLICT,
+            "booking was modified concurrently".to_string(),
+            Vec::new(),
+        ),
+        BookingError::Overlap(other) => (
+            http::StatusCode::CONFLICT,
+            format!("booking overlaps the reservation of booking {other}"),
+            Vec::new(),
+        ),
+    };
+    (
+        status,
+        ErrorView {
+            error: message,
+            fields,
+        },
+    )
+}
@@ -0,0 +1,175 @@
+//! Mapping of request paths onto the resources exposed by the HTTP API.
+
+use wasmcloud_component::http::Method;
+
+/// A resource addressed by a request path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    /// `/healthz`
+    Health,
+    /// `/bookings`
+    Bookings,
+    /// `/bookings:batch`
+    BookingsBatch,
+    /// `/bookings/{id}`
+    Booking(u32),
+    /// `/bookings/{id}/history`
+    BookingHistory(u32),
+    /// `/bookings/{id}/restore`
+    BookingRestore(u32),
+    /// `/resources/{resource}/availability`, with the resource name percent-decoded
+    Availability(String),
+    /// Any path not matching one of the above.
+    NotFound,
+}
+
+impl Route {
+    /// Resolve the route of a request path. A trailing slash is ignored.
+    pub fn parse(path: &str) -> Route {
+        let path = path.strip_suffix('/').unwrap_or(path);
+        let mut segments = path.strip_prefix('/').unwrap_or(path).split('/');
+        match (
+            segments.next(),
+            segments.next(),
+            segments.next(),
+            segments.next(),
+        ) {
+            (Some("healthz"), None, None, None) => Route::Health,
+            (Some("bookings"), None, None, None) => Route::Bookings,
+            (Some("bookings:batch"), None, None, None) => Route::BookingsBatch,
+            (Some("bookings"), Some(id), None, None) => {
+                parse_id(id).map_or(Route::NotFound, Route::Booking)
+            }
+            (Some("bookings"), Some(id), Some("history"), None) => {
+                parse_id(id).map_or(Route::NotFound, Route::BookingHistory)
+            }
+            (Some("bookings"), Some(id), Some("restore"), None) => {
+                parse_id(id).map_or(Route::NotFound, Route::BookingRestore)
+            }
+            (Some("resources"), Some(resource), Some("availability"), None) => {
+                decode(resource).map_or(Route::NotFound, Route::Availability)
+            }
+            _ => Route::NotFound,
+        }
+    }
+
+    /// The methods supported by the route.
+    pub fn allowed_methods(&self) -> &'static [Method] {
+        match self {
+            Route::Health | Route::BookingHistory(_) | Route::Availability(_) => &[Method::GET],
+            Route::BookingsBatch | Route::BookingRestore(_) => &[Method::POST],
+            Route::Bookings => &[Method::GET, Method::POST],
+            Route::Booking(_) => &[Method::GET, Method::POST, Method::PUT, Method::DELETE],
+            Route::NotFound => &[],
+        }
+    }
+}
+
+/// Parse a booking ID. Only the canonical form is accepted, without sign or leading zeros, so
+/// that every booking is addressed by a single path.
+fn parse_id(segment: &str) -> Option<u32> {
+    let canonical = segment.bytes().all(|b| b.is_ascii_digit())
+        && (segment == "0" || !segment.starts_with('0'));
+    canonical.then(|| segment.parse().ok()).flatten()
+}
+
+/// Decode a query parameter value, in which `+` stands for a space. Fails like [`decode`].
+pub fn decode_query(value: &str) -> Option<String> {
+    decode(&value.replace('+', " "))
+}
+
+/// Percent-decode a path segment. Fails on malformed escapes, invalid UTF-8 and empty segments.
+fn decode(segment: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(segment.len());
+    let mut rest = segment.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok().filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_booking_paths() {
+        assert_eq!(Route::parse("/healthz"), Route::Health);
+        assert_eq!(Route::parse("/bookings"), Route::Bookings);
+        assert_eq!(Route::parse("/bookings/"), Route::Bookings);
+        assert_eq!(Route::parse("/bookings:batch"), Route::BookingsBatch);
+        assert_eq!(Route::parse("/bookings/42"), Route::Booking(42));
+        assert_eq!(Route::parse("/bookings/0"), Route::Booking(0));
+        assert_eq!(
+            Route::parse("/bookings/42/history"),
+            Route::BookingHistory(42)
+        );
+        assert_eq!(
+            Route::parse("/bookings/42/restore/"),
+            Route::BookingRestore(42)
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_paths() {
+        for path in [
+            "/",
+            "/booking",
+            "/bookings/abc",
+            "/bookings/-1",
+            "/bookings/+42",
+            "/bookings/042",
+            "/bookings/4294967296",
+            "/bookings/042/history",
+            "/bookings/42/other",
+            "/bookings/42/history/1",
+            "/resources/room/other",
+        ] {
+            assert_eq!(Route::parse(path), Route::NotFound, "{path}");
+        }
+    }
+
+    #[test]
+    fn decodes_resource_names() {
+        assert_eq!(
+            Route::parse("/resources/room%2042/availability"),
+            Route::Availability("room 42".into())
+        );
+        assert_eq!(
+            Route::parse("/resources/r%C3%A4um/availability"),
+            Route::Availability("räum".into())
+        );
+        assert_eq!(Route::parse("/resources/%zz/availability"), Route::NotFound);
+        assert_eq!(Route::parse("/resources//availability"), Route::NotFound);
+    }
+
+    #[test]
+    fn decodes_query_values() {
+        assert_eq!(decode_query("room+42").as_deref(), Some("room 42"));
+        assert_eq!(decode_query("a%2Bb").as_deref(), Some("a+b"));
+        assert_eq!(decode_query("%E2%82"), None);
+        assert_eq!(decode_query(""), None);
+    }
+
+    #[test]
+    fn lists_allowed_methods() {
+        assert_eq!(
+            Route::Bookings.allowed_methods(),
+            [Method::GET, Method::POST]
+        );
+        assert_eq!(
+            Route::Availability("room".into()).allowed_methods(),
+            [Method::GET]
+        );
+        assert_eq!(Route::Booking(42).allowed_methods().len(), 4);
+        assert_eq!(Route::BookingsBatch.allowed_methods(), [Method::POST]);
+        assert!(Route::NotFound.allowed_methods().is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+//! Booking API served as request/reply over the messaging bus.
+//!
+//! Requests are sent to `bookings.rpc.<operation>` and answered on their reply subject with the
+//! status and body of the matching HTTP response, as `{"status": 404, "body": {...}}`, so clients
+//! can use either transport with the same JSON contract. Every request may name the tenant it is
+//! made for in a `tenant` field, in place of the `X-Tenant-Id` header. Requests carry no token: who
+//! may send them is governed by the permissions of the NATS account.
+//!
+//! The handler also receives the triggers of the [`maintenance`](crate::maintenance) tasks.
+//!
+//! | Subject               | Request                          | HTTP equivalent          |
+//! | --------------------- | -------------------------------- | ------------------------ |
+//! | `bookings.rpc.get`    | `{"id": 42}`                     | `GET /bookings/42`       |
+//! | `bookings.rpc.add`    | `{"id": 42, "booking": {...}}`   | `POST /bookings/42`      |
+//! | `bookings.rpc.create` | `{"booking": {...}}`             | `POST /bookings`         |
+//! | `bookings.rpc.delete` | `{"id": 42}`                     | `DELETE /bookings/42`    |
+
+use serde::Deserialize;
+use wasmcloud_component::http;
+
+use crate::exports::wasmcloud::messaging::handler::Guest;
+use crate::maintenance;
+use crate::model::{NewBooking, ReplyView};
+use crate::wasmcloud::messaging::{consumer, types};
+use crate::{log, response, validation, Component, Level, LOG_CONTEXT};
+
+/// Prefix of the subjects requests are received on.
+const SUBJECT_PREFIX: &str = "bookings.rpc.";
+
+/// Request addressing a booking by its ID.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IdRequest {
+    #[serde(default)]
+    tenant: String,
+    id: u32,
+}
+
+/// Request to create a booking under an ID allocated by the booking master.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CreateRequest {
+    #[serde(default)]
+    tenant: String,
+    booking: NewBooking,
+}
+
+/// Request to add a booking.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AddRequest {
+    #[serde(default)]
+    tenant: String,
+    id: u32,
+    booking: NewBooking,
+}
+
+/// Encode the reply carrying `response`.
+fn reply(response: http::Response<String>) -> Result<Vec<u8>, String> {
+    let status = response.status().as_u16();
+    let body = response.into_body();
+    let body = if body.is_empty() {
+        None
+    } else {
+        // all responses are JSON, a body failing to parse is passed on as string
+        Some(serde_json::from_str(&body).unwrap_or(serde_json::Value::String(body)))
+    };
+    serde_json::to_vec(&ReplyView { status, body })
+        .map_err(|e| format!("failed to encode reply: {e}"))
+}
+
+impl Component {
+    /// Serve the request received on `subject` like the matching HTTP request.
+    fn dispatch(subject: &str, body: &[u8]) -> http::Result<http::Response<String>> {
+        match subject.strip_prefix(SUBJECT_PREFIX) {
+            Some("get") => match validation::parse::<IdRequest>(body, validation::ID_REQUEST) {
+                Ok(request) => {
+                    Self::get_booking(&request.tenant, request.id, &http::HeaderMap::new())
+                }
+                Err(e) => response::invalid(e),
+            },
+            Some("add") => {
+                match validation::parse::<AddRequest>(body, validation::ID_BOOKING_REQUEST) {
+                    Ok(request) => Self::add_booking(&request.tenant, request.id, request.booking),
+                    Err(e) => response::invalid(e),
+                }
+            }
+            Some("create") => {
+                match validation::parse::<CreateRequest>(body, validation::CREATE_REQUEST) {
+                    Ok(request) => Self::create_booking(&request.tenant, request.booking),
+                    Err(e) => response::invalid(e),
+                }
+            }
+            Some("delete") => match validation::parse::<IdRequest>(body, validation::ID_REQUEST) {
+                Ok(request) => Self::delete_booking(&request.tenant, request.id),
+                Err(e) => response::invalid(e),
+            },
+            _ => response::error(
+                http::StatusCode::NOT_FOUND,
+                format!("no such operation: {subject}"),
+            ),
+        }
+    }
+}
+
+impl Guest for Component {
+    fn handle_message(msg: types::BrokerMessage) -> Result<(), String> {
+        if let Some(reply) = maintenance::run(&msg.subject) {
+            let reply = reply?;
+            return match msg.reply_to {
+                Some(reply_to) => consumer::publish(&types::BrokerMessage {
+                    subject: reply_to,
+                    reply_to: None,
+                    body: reply,
+                }),
+                None => Ok(()),
+            };
+        }
+        let Some(reply_to) = msg.reply_to else {
+            log(
+                Level::Warn,
+                LOG_CONTEXT,
+                &format!("ignoring message on {} without reply subject", msg.subject),
+            );
+            return Err(format!("message on {} has no reply subject", msg.subject));
+        };
+        let response = Self::dispatch(&msg.subject, &msg.body)
+            .map_err(|e| format!("failed to handle request on {}: {e:?}", msg.subject))?;
+        log(
+            Level::Info,
+            LOG_CONTEXT,
+            &format!(
+                "handled request on {} with status {}",
+                msg.subject,
+                response.status()
+            ),
+        );
+        consumer::publish(&types::BrokerMessage {
+            subject: reply_to,
+            reply_to: None,
+            body: reply(response)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(response: http::Result<http::Response<String>>) -> serde_json::Value {
+        serde_json::from_slice(&reply(response.unwrap()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn reply_carries_status_and_body() {
+        assert_eq!(
+            decode(response::error(
+                http::StatusCode::NOT_FOUND,
+                "no such booking"
+            )),
+            serde_json::json!({"status": 404, "body": {"error": "no such booking"}})
+        );
+    }
+
+    #[test]
+    fn reply_omits_empty_body() {
+        let response = http::Response::builder()
+            .status(http::StatusCode::NO_CONTENT)
+            .body(String::new())
+            .map_err(|e| http::ErrorCode::InternalError(Some(e.to_string())));
+        assert_eq!(decode(response), serde_json::json!({"status": 204}));
+    }
+}
@@ -0,0 +1,47 @@
+//! Formatting of UNIX timestamps.
+
+/// Split seconds since the UNIX epoch into a civil date and the seconds into that day.
+///
+/// Uses the algorithm described in <https://howardhinnant.github.io/date_algorithms.html>.
+fn civil(secs: u64) -> (i64, u32, u32, u64) {
+    let days = (secs / 86_400) as i64;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day, secs % 86_400)
+}
+
+/// Format a timestamp as RFC 3339 date-time in UTC, e.g. `2025-10-18T09:30:00Z`.
+pub fn rfc3339(secs: u64) -> String {
+    let (year, month, day, time) = civil(secs);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
+
+/// Format a timestamp as an HTTP date, e.g. `Sat, 18 Oct 2025 09:30:00 GMT`.
+pub fn http_date(secs: u64) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let (year, month, day, time) = civil(secs);
+    format!(
+        "{}, {day:02} {} {year:04} {:02}:{:02}:{:02} GMT",
+        // the UNIX epoch was a Thursday
+        WEEKDAYS[(secs / 86_400 % 7) as usize],
+        MONTHS[month as usize - 1],
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
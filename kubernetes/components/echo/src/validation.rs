@@ -0,0 +1,297 @@
+//! Validation of request bodies against the schema of the API.
+//!
+//! Bodies are checked before they are deserialized, so that every failing field is reported at
+//! once, by its path in the document (e.g. `booking.start`). The booking master checks the content
+//! of the fields, such as their length, and reports failures the same way. Optional fields set to
+//! `null` are treated as absent.
+
+use std::io::Read;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::model::FieldErrorView;
+
+/// Maximum size of a request body, in bytes.
+pub const MAX_BODY_SIZE: usize = 64 * 1024;
+/// Maximum number of items of every operation of a batch request.
+const MAX_BATCH_SIZE: usize = 100;
+
+/// Shape of the value of a field.
+pub enum Kind {
+    Text,
+    /// Unsigned integer up to the given maximum.
+    Integer(u64),
+    /// One of the given strings.
+    OneOf(&'static [&'static str]),
+    Object(&'static [Field]),
+    /// List of at most the given number of values of the given kind.
+    List(&'static Kind, usize),
+}
+
+/// A field of a JSON object. Fields not listed in a schema are rejected.
+pub struct Field {
+    name: &'static str,
+    kind: Kind,
+    required: bool,
+}
+
+const BOOKING: &[Field] = &[
+    Field {
+        name: "subject",
+        kind: Kind::Text,
+        required: true,
+    },
+    Field {
+        name: "guest_name",
+        kind: Kind::Text,
+        required: false,
+    },
+    Field {
+        name: "resource",
+        kind: Kind::Text,
+        required: false,
+    },
+    Field {
+        name: "start",
+        kind: Kind::Integer(u64::MAX),
+        required: false,
+    },
+    Field {
+        name: "end",
+        kind: Kind::Integer(u64::MAX),
+        required: false,
+    },
+    Field {
+        name: "status",
+        kind: Kind::OneOf(&["pending", "confirmed", "cancelled"]),
+        required: false,
+    },
+    Field {
+        name: "expires_at",
+        kind: Kind::Integer(u64::MAX),
+        required: false,
+    },
+];
+
+const ID: Field = Field {
+    name: "id",
+    kind: Kind::Integer(u32::MAX as u64),
+    required: true,
+};
+
+const TENANT: Field = Field {
+    name: "tenant",
+    kind: Kind::Text,
+    required: false,
+};
+
+const BOOKING_FIELD: Field = Field {
+    name: "booking",
+    kind: Kind::Object(BOOKING),
+    required: true,
+};
+
+const ID_BOOKING: &[Field] = &[ID, BOOKING_FIELD];
+
+/// `{"booking": {...}}`
+pub const BOOKING_REQUEST: &[Field] = &[BOOKING_FIELD];
+/// `{"tenant": "t", "booking": {...}}`, where `tenant` is optional
+pub const CREATE_REQUEST: &[Field] = &[TENANT, BOOKING_FIELD];
+/// `{"tenant": "t", "id": 42}`, where `tenant` is optional
+pub const ID_REQUEST: &[Field] = &[TENANT, ID];
+/// `{"tenant": "t", "id": 42, "booking": {...}}`, where `tenant` is optional
+pub const ID_BOOKING_REQUEST: &[Field] = &[TENANT, ID, BOOKING_FIELD];
+
+/// `{"add": [{"id": 42, "booking": {...}}], "get": [42], "delete": [42]}`, where every operation
+/// is optional
+pub const BATCH_REQUEST: &[Field] = &[
+    Field {
+        name: "add",
+        kind: Kind::List(&Kind::Object(ID_BOOKING), MAX_BATCH_SIZE),
+        required: false,
+    },
+    Field {
+        name: "get",
+        kind: Kind::List(&Kind::Integer(u32::MAX as u64), MAX_BATCH_SIZE),
+        required: false,
+    },
+    Field {
+        name: "delete",
+        kind: Kind::List(&Kind::Integer(u32::MAX as u64), MAX_BATCH_SIZE),
+        required: false,
+    },
+];
+
+/// Read a request body, stopping one byte past [`MAX_BODY_SIZE`] so that [`parse`] rejects it.
+pub fn read_body(body: impl Read) -> Result<Vec<u8>, Vec<FieldErrorView>> {
+    let mut bytes = Vec::new();
+    body.take(MAX_BODY_SIZE as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| vec![error("body", format!("could not be read: {e}"))])?;
+    Ok(bytes)
+}
+
+/// Check a JSON body against `schema` and deserialize it.
+pub fn parse<T: DeserializeOwned>(body: &[u8], schema: &[Field]) -> Result<T, Vec<FieldErrorView>> {
+    if body.len() > MAX_BODY_SIZE {
+        return Err(vec![error(
+            "body",
+            format!("must be at most {MAX_BODY_SIZE} bytes"),
+        )]);
+    }
+    let mut value: Value = serde_json::from_slice(body)
+        .map_err(|e| vec![error("body", format!("is not valid JSON: {e}"))])?;
+    let mut errors = Vec::new();
+    check_object(&mut value, "", schema, &mut errors);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    serde_json::from_value(value).map_err(|e| vec![error("body", e.to_string())])
+}
+
+fn error(field: &str, message: impl Into<String>) -> FieldErrorView {
+    FieldErrorView {
+        field: field.to_string(),
+        message: message.into(),
+    }
+}
+
+fn path(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{parent}.{name}")
+    }
+}
+
+fn check_object(value: &mut Value, at: &str, schema: &[Field], errors: &mut Vec<FieldErrorView>) {
+    let Some(object) = value.as_object_mut() else {
+        errors.push(error(
+            if at.is_empty() { "body" } else { at },
+            "must be an object",
+        ));
+        return;
+    };
+    object.retain(|name, value| {
+        !value.is_null()
+            || schema
+                .iter()
+                .any(|field| field.name == name && field.required)
+    });
+    for field in schema {
+        match object.get_mut(field.name) {
+            Some(value) => check(value, &path(at, field.name), &field.kind, errors),
+            None if field.required => errors.push(error(&path(at, field.name), "is required")),
+            None => {}
+        }
+    }
+    for name in object.keys() {
+        if !schema.iter().any(|field| field.name == name) {
+            errors.push(error(&path(at, name), "is not a known field"));
+        }
+    }
+}
+
+fn check(value: &mut Value, at: &str, kind: &Kind, errors: &mut Vec<FieldErrorView>) {
+    match kind {
+        Kind::Text if !value.is_string() => errors.push(error(at, "must be a string")),
+        Kind::Integer(max) if value.as_u64().is_none_or(|n| n > *max) => {
+            errors.push(error(at, format!("must be an integer from 0 to {max}")));
+        }
+        Kind::OneOf(allowed) if !value.as_str().is_some_and(|s| allowed.contains(&s)) => {
+            errors.push(error(at, format!("must be one of {}", allowed.join(", "))));
+        }
+        Kind::Object(schema) => check_object(value, at, schema, errors),
+        Kind::List(item, max) => match value.as_array_mut() {
+            None => errors.push(error(at, "must be a list")),
+            Some(items) if items.len() > *max => {
+                errors.push(error(at, format!("must have at most {max} items")));
+            }
+            Some(items) => {
+                for (i, value) in items.iter_mut().enumerate() {
+                    check(value, &format!("{at}[{i}]"), item, errors);
+                }
+            }
+        },
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::NewBooking;
+
+    /// The paths of the fields failing when `body` is parsed against `schema`.
+    fn failing(body: &str, schema: &[Field]) -> Vec<String> {
+        match parse::<Value>(body.as_bytes(), schema) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.into_iter().map(|e| e.field).collect(),
+        }
+    }
+
+    #[test]
+    fn accepts_valid_bookings() {
+        let body = r#"{"booking": {"subject": "Room", "resource": "room-1", "start": 1, "end": 2,
+            "status": "confirmed"}}"#;
+        assert!(failing(body, BOOKING_REQUEST).is_empty());
+        assert!(failing(r#"{"id": 42}"#, ID_REQUEST).is_empty());
+    }
+
+    #[test]
+    fn reports_every_failing_field() {
+        let body = r#"{"booking": {"guest_name": 1, "start": -1, "status": "done", "room": "a"},
+            "id": 1}"#;
+        assert_eq!(
+            failing(body, BOOKING_REQUEST),
+            [
+                "booking.subject",
+                "booking.guest_name",
+                "booking.start",
+                "booking.status",
+                "booking.room",
+                "id",
+            ]
+        );
+        assert_eq!(failing(r#"{"id": 4294967296}"#, ID_REQUEST), ["id"]);
+        assert_eq!(
+            failing(r#"{"booking": []}"#, ID_BOOKING_REQUEST),
+            ["id", "booking"]
+        );
+    }
+
+    #[test]
+    fn treats_null_optional_fields_as_absent() {
+        let body = r#"{"subject": "Room", "guest_name": null, "expires_at": null}"#;
+        let booking = parse::<NewBooking>(body.as_bytes(), BOOKING).unwrap();
+        assert_eq!(booking.guest_name, "");
+        assert_eq!(booking.expires_at, None);
+        assert_eq!(
+            failing(r#"{"booking": null}"#, BOOKING_REQUEST),
+            ["booking"]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_bodies() {
+        assert_eq!(failing("{", BOOKING_REQUEST), ["body"]);
+        assert_eq!(failing("[]", BOOKING_REQUEST), ["body"]);
+        let oversized = format!(r#"{{"id": 1{}}}"#, " ".repeat(MAX_BODY_SIZE));
+        assert_eq!(failing(&oversized, ID_REQUEST), ["body"]);
+    }
+
+    #[test]
+    fn checks_batch_items() {
+        let body = r#"{"add": [{"id": 1, "booking": {}}], "get": [1, "2"], "delete": 3}"#;
+        assert_eq!(
+            failing(body, BATCH_REQUEST),
+            ["add[0].booking.subject", "get[1]", "delete"]
+        );
+        let ids = vec!["1"; MAX_BATCH_SIZE + 1].join(", ");
+        assert_eq!(
+            failing(&format!(r#"{{"get": [{ids}]}}"#), BATCH_REQUEST),
+            ["get"]
+        );
+    }
+}